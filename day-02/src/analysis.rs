@@ -0,0 +1,184 @@
+use std::fmt::Display;
+
+use crate::Hand;
+
+/// Payoff for playing own hand (row) against the opponent's hand (column)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PayoffTable([[f64; 3]; 3]);
+
+impl Default for PayoffTable {
+	/// The puzzle scoring: shape score + round score
+	fn default() -> Self {
+		let mut table = [[0.0; 3]; 3];
+		for (i, own) in Hand::ALL.iter().enumerate() {
+			for (j, op) in Hand::ALL.iter().enumerate() {
+				table[i][j] = own.score(op) as f64;
+			}
+		}
+		PayoffTable(table)
+	}
+}
+
+impl PayoffTable {
+	/// Rows and columns are indexed in `Hand::ALL` order
+	pub fn new(table: [[f64; 3]; 3]) -> Self {
+		PayoffTable(table)
+	}
+
+	pub fn get(&self, own: Hand, op: Hand) -> f64 {
+		self.0[own as usize - 1][op as usize - 1]
+	}
+
+	/// Expected payoff of the mixed strategy `mix` against `op`
+	fn expected(&self, mix: &[f64; 3], op: Hand) -> f64 {
+		Hand::ALL
+			.iter()
+			.zip(mix)
+			.map(|(own, p)| p * self.get(*own, op))
+			.sum()
+	}
+
+	/// Symmetric Nash equilibrium, the opponent scoring with the same table: a mixed strategy
+	/// that is a best response to itself, and the payoff per round when both play it.
+	///
+	/// Every hand played with some probability must pay the same against the mix, and no other
+	/// hand more. Supports are tried from the largest, solving for the mix that makes the
+	/// support's hands pay the same, and the first one that is a best response is returned.
+	/// A symmetric game always has one.
+	pub fn equilibrium(&self) -> ([f64; 3], f64) {
+		const EPS: f64 = 1e-9;
+		let mut supports: Vec<u8> = (1..8).collect();
+		supports.sort_by_key(|s| std::cmp::Reverse(s.count_ones()));
+		for support in supports {
+			let played = |i: usize| support & (1 << i) != 0;
+			// unknowns p0, p1, p2 and the value v
+			let mut m = [[0.0; 4]; 4];
+			for (i, (row, payoffs)) in m.iter_mut().zip(&self.0).enumerate() {
+				if played(i) {
+					// sum_j table[i][j] * p_j - v = 0
+					*row = [payoffs[0], payoffs[1], payoffs[2], -1.0];
+				} else {
+					row[i] = 1.0;
+				}
+			}
+			m[3] = [1.0, 1.0, 1.0, 0.0];
+			let Some([p0, p1, p2, value]) = solve(m, [0.0, 0.0, 0.0, 1.0]) else {
+				continue;
+			};
+			let mix = [p0, p1, p2];
+			if mix.iter().any(|&p| p < -EPS) {
+				continue;
+			}
+			let mix = mix.map(|p| p.max(0.0));
+			if Hand::ALL.iter().all(|own| {
+				let pays: f64 = Hand::ALL
+					.iter()
+					.zip(&mix)
+					.map(|(op, p)| p * self.get(*own, *op))
+					.sum();
+				pays <= value + EPS
+			}) {
+				return (mix, value);
+			}
+		}
+		unreachable!("a symmetric game has a symmetric equilibrium")
+	}
+}
+
+/// Solve `m * x = rhs` with Gaussian elimination, `None` if `m` is singular
+fn solve<const N: usize>(mut m: [[f64; N]; N], mut rhs: [f64; N]) -> Option<[f64; N]> {
+	for col in 0..N {
+		let pivot = (col..N).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))?;
+		if m[pivot][col].abs() < 1e-12 {
+			return None;
+		}
+		m.swap(col, pivot);
+		rhs.swap(col, pivot);
+		for row in 0..N {
+			if row != col {
+				let f = m[row][col] / m[col][col];
+				let pivot_row = m[col];
+				for (x, p) in m[row].iter_mut().zip(pivot_row) {
+					*x -= f * p;
+				}
+				rhs[row] -= f * rhs[col];
+			}
+		}
+	}
+	Some(std::array::from_fn(|i| rhs[i] / m[i][i]))
+}
+
+/// Best and worst answer to one opponent hand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Choice {
+	pub opponent: Hand,
+	pub best: Hand,
+	pub worst: Hand,
+}
+
+/// What could be scored against a known opponent column
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+	pub table: PayoffTable,
+	pub rounds: Vec<Choice>,
+	pub max: f64,
+	pub min: f64,
+}
+
+impl Analysis {
+	pub fn new(opponents: &[Hand], table: PayoffTable) -> Self {
+		let (mut max, mut min) = (0.0, 0.0);
+		let mut rounds = vec![];
+		for &op in opponents {
+			let by_payoff =
+				|a: &&Hand, b: &&Hand| table.get(**a, op).total_cmp(&table.get(**b, op));
+			let best = *Hand::ALL.iter().max_by(by_payoff).unwrap();
+			let worst = *Hand::ALL.iter().min_by(by_payoff).unwrap();
+			max += table.get(best, op);
+			min += table.get(worst, op);
+			rounds.push(Choice {
+				opponent: op,
+				best,
+				worst,
+			});
+		}
+		Analysis {
+			table,
+			rounds,
+			max,
+			min,
+		}
+	}
+
+	/// Expected total when every round is played with the mixed strategy `mix`
+	/// (probabilities of Rock, Paper, Scissors)
+	pub fn expected(&self, mix: [f64; 3]) -> f64 {
+		self.rounds
+			.iter()
+			.map(|c| self.table.expected(&mix, c.opponent))
+			.sum()
+	}
+}
+
+impl Display for Analysis {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for (i, c) in self.rounds.iter().enumerate() {
+			let (best, worst) = (
+				self.table.get(c.best, c.opponent),
+				self.table.get(c.worst, c.opponent),
+			);
+			writeln!(
+				f,
+				"[{i}]: {:?} => best {:?} ({best}), worst {:?} ({worst})",
+				c.opponent, c.best, c.worst
+			)?;
+		}
+		writeln!(f, "max: {}, min: {}", self.max, self.min)?;
+		let (mix, value) = self.table.equilibrium();
+		write!(
+			f,
+			"equilibrium: Rock {:.3}, Paper {:.3}, Scissors {:.3} (value {value:.3} per round)",
+			mix[0], mix[1], mix[2]
+		)
+	}
+}
@@ -1,26 +1,33 @@
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-enum Hand {
+use std::collections::HashMap;
+
+pub mod analysis;
+pub mod tournament;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Hand {
 	Rock = 1,
 	Paper = 2,
 	Scissors = 3,
 }
 
-#[derive(PartialEq, Copy, Clone)]
-enum Round {
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Round {
 	Win = 6,
 	Draw = 3,
 	Loss = 0,
 }
 
 impl Hand {
-	fn beats(&self) -> Hand {
+	pub const ALL: [Hand; 3] = [Hand::Rock, Hand::Paper, Hand::Scissors];
+
+	pub fn beats(&self) -> Hand {
 		match &self {
 			Hand::Rock => Hand::Scissors,
 			Hand::Paper => Hand::Rock,
 			Hand::Scissors => Hand::Paper,
 		}
 	}
-	fn loses(&self) -> Hand {
+	pub fn loses(&self) -> Hand {
 		match &self {
 			Hand::Rock => Hand::Paper,
 			Hand::Paper => Hand::Scissors,
@@ -28,7 +35,21 @@ impl Hand {
 		}
 	}
 
-	fn vs(&self, op: &Hand) -> Round {
+	/// Hand `n` steps ahead of self (Rock -> Paper -> Scissors -> Rock)
+	pub fn shift(&self, n: usize) -> Hand {
+		Hand::ALL[(*self as usize - 1 + n) % 3]
+	}
+
+	/// Hand to play against self to get the desired round
+	pub fn for_round(&self, round: Round) -> Hand {
+		match round {
+			Round::Draw => *self,
+			Round::Loss => self.beats(),
+			Round::Win => self.loses(),
+		}
+	}
+
+	pub fn vs(&self, op: &Hand) -> Round {
 		if self.beats() == *op {
 			Round::Win
 		} else if op.beats() == *self {
@@ -39,60 +60,238 @@ impl Hand {
 	}
 
 	/// Score gained for self after playing one round of self vs op
-	fn score(&self, op: &Hand) -> usize {
+	pub fn score(&self, op: &Hand) -> usize {
 		let shape_score = *self as usize;
 		let round_score = self.vs(op) as usize;
 		shape_score + round_score
 	}
 
-	fn from_str(x: &str) -> Self {
+	fn from_name(x: &str) -> Option<Self> {
 		match x {
-			"A" | "X" => Hand::Rock,
-			"B" | "Y" => Hand::Paper,
-			"C" | "Z" => Hand::Scissors,
-			_ => unreachable!("Can't create Hand::{{}} from_str: {x}"),
+			"rock" => Some(Hand::Rock),
+			"paper" => Some(Hand::Paper),
+			"scissors" => Some(Hand::Scissors),
+			_ => None,
 		}
 	}
 }
 
 impl Round {
-	fn from_str(x: &str) -> Self {
+	fn from_name(x: &str) -> Option<Self> {
 		match x {
-			"X" => Round::Loss,
-			"Y" => Round::Draw,
-			"Z" => Round::Win,
-			_ => unreachable!("Can't create Round::{{}} from_str: {x}"),
+			"loss" => Some(Round::Loss),
+			"draw" => Some(Round::Draw),
+			"win" => Some(Round::Win),
+			_ => None,
 		}
 	}
 }
 
-pub fn p1(lines: &Vec<String>) -> usize {
-	let mut total = 0;
-	for line in lines {
-		let mut words = line.split_whitespace();
-		let hand_opp = Hand::from_str(words.next().unwrap());
-		let hand_own = Hand::from_str(words.next().unwrap());
-		let s = hand_own.score(&hand_opp);
-		total += s;
+/// What a symbol in the second column of the strategy guide tells us to play
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Strategy {
+	/// play this shape
+	Shape(Hand),
+	/// play whatever gets this outcome against the opponent
+	Outcome(Round),
+	/// play the shape `n` steps ahead of the opponent's
+	Shift(usize),
+}
+
+impl Strategy {
+	pub fn play(&self, op: &Hand) -> Hand {
+		match self {
+			Strategy::Shape(hand) => *hand,
+			Strategy::Outcome(round) => op.for_round(*round),
+			Strategy::Shift(n) => op.shift(*n),
+		}
 	}
-	total
 }
 
-pub fn p2(lines: &Vec<String>) -> usize {
-	let mut total = 0;
-	for line in lines {
+#[derive(Debug)]
+pub enum DecodeError {
+	MissingColumn(String),
+	UnknownSymbol(String),
+	/// config line number (1-based) and its content
+	BadConfig(usize, String),
+	Io(std::io::Error),
+}
+
+/// Turns strategy guide lines into (opponent, own) hands using a symbol mapping table.
+///
+/// The table can be loaded from a config file with one `<column> <symbol> <meaning>` per line:
+/// ```text
+/// # comments and empty lines are ignored
+/// opponent A rock
+/// own X loss
+/// own Y draw
+/// own Z shift 1
+/// ```
+/// `<meaning>` is a shape (`rock`, `paper`, `scissors`) for both columns, and additionally an
+/// outcome (`loss`, `draw`, `win`) or `shift <n>` for the `own` column.
+#[derive(Debug, Clone, Default)]
+pub struct StrategyDecoder {
+	opponent: HashMap<String, Hand>,
+	own: HashMap<String, Strategy>,
+}
+
+impl StrategyDecoder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Part 1 reading: `A/B/C` and `X/Y/Z` are both shapes
+	pub fn shapes() -> Self {
+		Self::new()
+			.with_abc()
+			.map_own("X", Strategy::Shape(Hand::Rock))
+			.map_own("Y", Strategy::Shape(Hand::Paper))
+			.map_own("Z", Strategy::Shape(Hand::Scissors))
+	}
+
+	/// Part 2 reading: `X/Y/Z` are the desired outcome
+	pub fn outcomes() -> Self {
+		Self::new()
+			.with_abc()
+			.map_own("X", Strategy::Outcome(Round::Loss))
+			.map_own("Y", Strategy::Outcome(Round::Draw))
+			.map_own("Z", Strategy::Outcome(Round::Win))
+	}
+
+	fn with_abc(self) -> Self {
+		self.map_opponent("A", Hand::Rock)
+			.map_opponent("B", Hand::Paper)
+			.map_opponent("C", Hand::Scissors)
+	}
+
+	pub fn map_opponent(mut self, symbol: &str, hand: Hand) -> Self {
+		self.opponent.insert(symbol.to_string(), hand);
+		self
+	}
+
+	pub fn map_own(mut self, symbol: &str, strategy: Strategy) -> Self {
+		self.own.insert(symbol.to_string(), strategy);
+		self
+	}
+
+	pub fn from_config(config: &str) -> Result<Self, DecodeError> {
+		let mut decoder = Self::new();
+		for (i, line) in config.lines().enumerate() {
+			let line = line.split('#').next().unwrap().trim();
+			if line.is_empty() {
+				continue;
+			}
+			let bad = || DecodeError::BadConfig(i + 1, line.to_string());
+			let words: Vec<&str> = line.split_whitespace().collect();
+			match words.as_slice() {
+				["opponent", symbol, name] => {
+					let hand = Hand::from_name(name).ok_or_else(bad)?;
+					decoder = decoder.map_opponent(symbol, hand);
+				}
+				["own", symbol, "shift", n] => {
+					let n = n.parse::<usize>().map_err(|_| bad())?;
+					decoder = decoder.map_own(symbol, Strategy::Shift(n));
+				}
+				["own", symbol, name] => {
+					let strategy = if let Some(hand) = Hand::from_name(name) {
+						Strategy::Shape(hand)
+					} else if let Some(round) = Round::from_name(name) {
+						Strategy::Outcome(round)
+					} else {
+						return Err(bad());
+					};
+					decoder = decoder.map_own(symbol, strategy);
+				}
+				_ => return Err(bad()),
+			}
+		}
+		Ok(decoder)
+	}
+
+	pub fn from_file(path: &str) -> Result<Self, DecodeError> {
+		let config = std::fs::read_to_string(path).map_err(DecodeError::Io)?;
+		Self::from_config(&config)
+	}
+
+	/// Decode one guide line into the (opponent, own) hands
+	pub fn decode(&self, line: &str) -> Result<(Hand, Hand), DecodeError> {
 		let mut words = line.split_whitespace();
-		let hand_opp = Hand::from_str(words.next().unwrap());
-		let desired_round = Round::from_str(words.next().unwrap());
-		let hand_own = match desired_round {
-			Round::Draw => hand_opp,
-			Round::Loss => hand_opp.beats(),
-			Round::Win => hand_opp.loses(),
-		};
-		let s = hand_own.score(&hand_opp);
-		total += s;
-	}
-	total
+		let missing = || DecodeError::MissingColumn(line.to_string());
+		let (opp, own) = (
+			words.next().ok_or_else(missing)?,
+			words.next().ok_or_else(missing)?,
+		);
+		let hand_opp = *self
+			.opponent
+			.get(opp)
+			.ok_or_else(|| DecodeError::UnknownSymbol(opp.to_string()))?;
+		let strategy = self
+			.own
+			.get(own)
+			.ok_or_else(|| DecodeError::UnknownSymbol(own.to_string()))?;
+		Ok((hand_opp, strategy.play(&hand_opp)))
+	}
+
+	/// Only the opponent's column of the guide
+	pub fn opponents(&self, lines: &[String]) -> Result<Vec<Hand>, DecodeError> {
+		let mut v = vec![];
+		for line in lines {
+			let opp = line
+				.split_whitespace()
+				.next()
+				.ok_or_else(|| DecodeError::MissingColumn(line.to_string()))?;
+			let hand = *self
+				.opponent
+				.get(opp)
+				.ok_or_else(|| DecodeError::UnknownSymbol(opp.to_string()))?;
+			v.push(hand);
+		}
+		Ok(v)
+	}
+
+	/// Total score for following the whole guide
+	pub fn score(&self, lines: &[String]) -> Result<usize, DecodeError> {
+		let mut total = 0;
+		for line in lines {
+			let (hand_opp, hand_own) = self.decode(line)?;
+			total += hand_own.score(&hand_opp);
+		}
+		Ok(total)
+	}
+
+	/// Score the guide for every way of reading `symbols` as the three shapes.
+	/// Returns the shapes assigned to `symbols` (in order) together with the total score.
+	pub fn score_permutations(
+		&self,
+		symbols: [&str; 3],
+		lines: &[String],
+	) -> Result<Vec<([Hand; 3], usize)>, DecodeError> {
+		let mut v = vec![];
+		for (a, b, c) in [
+			(0, 1, 2),
+			(0, 2, 1),
+			(1, 0, 2),
+			(1, 2, 0),
+			(2, 0, 1),
+			(2, 1, 0),
+		] {
+			let hands = [Hand::ALL[a], Hand::ALL[b], Hand::ALL[c]];
+			let mut decoder = self.clone();
+			for (symbol, hand) in symbols.iter().zip(hands) {
+				decoder = decoder.map_own(symbol, Strategy::Shape(hand));
+			}
+			v.push((hands, decoder.score(lines)?));
+		}
+		Ok(v)
+	}
+}
+
+pub fn p1(lines: &[String]) -> usize {
+	StrategyDecoder::shapes().score(lines).unwrap()
+}
+
+pub fn p2(lines: &[String]) -> usize {
+	StrategyDecoder::outcomes().score(lines).unwrap()
 }
 
 #[cfg(test)]
@@ -143,4 +342,114 @@ mod tests {
 		let r = p2(&lines);
 		assert_eq!(r, 10274);
 	}
+
+	#[test]
+	fn config() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let d = StrategyDecoder::from_file("shapes.cfg").unwrap();
+		assert_eq!(d.score(&lines).unwrap(), 15);
+		let d = StrategyDecoder::from_file("outcomes.cfg").unwrap();
+		assert_eq!(d.score(&lines).unwrap(), 12);
+		// shift 2 is the hand the opponent beats, i.e. a loss
+		let d = StrategyDecoder::from_config("opponent A rock\nown X shift 2").unwrap();
+		assert_eq!(d.decode("A X").unwrap(), (Hand::Rock, Hand::Scissors));
+		assert!(StrategyDecoder::from_config("own X lizard").is_err());
+		assert!(d.decode("B X").is_err());
+	}
+
+	#[test]
+	fn permutations() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let scores = StrategyDecoder::shapes()
+			.score_permutations(["X", "Y", "Z"], &lines)
+			.unwrap();
+		assert_eq!(scores.len(), 6);
+		assert_eq!(scores[0], ([Hand::Rock, Hand::Paper, Hand::Scissors], 15));
+	}
+
+	#[test]
+	fn analysis() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let opponents = StrategyDecoder::shapes().opponents(&lines).unwrap();
+		let a = analysis::Analysis::new(&opponents, analysis::PayoffTable::default());
+		assert_eq!((a.max, a.min), (24.0, 6.0));
+		assert_eq!(a.rounds[0].best, Hand::Paper);
+		assert!((a.expected([1.0 / 3.0; 3]) - 15.0).abs() < 1e-9);
+		let text = a.to_string();
+		assert_eq!(text.lines().count(), 5);
+		assert!(text.starts_with("[0]: Rock => best Paper (8), worst Scissors (3)\n"));
+		assert!(text.contains("\nmax: 24, min: 6\n"));
+		assert!(text.ends_with(
+			"equilibrium: Rock 0.444, Paper 0.111, Scissors 0.444 (value 5.000 per round)"
+		));
+
+		// classic zero-sum game: uniform play, value 0
+		let table =
+			analysis::PayoffTable::new([[0.0, -1.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 1.0, 0.0]]);
+		let (mix, value) = table.equilibrium();
+		assert!(mix.iter().all(|p| (p - 1.0 / 3.0).abs() < 1e-9));
+		assert!(value.abs() < 1e-9);
+
+		// the puzzle table isn't zero-sum: each hand pays 5 against Rock 4/9, Paper 1/9,
+		// Scissors 4/9, so that mix is a best response to itself
+		let (mix, value) = analysis::PayoffTable::default().equilibrium();
+		for (p, want) in mix.iter().zip([4.0 / 9.0, 1.0 / 9.0, 4.0 / 9.0]) {
+			assert!((p - want).abs() < 1e-9);
+		}
+		assert!((value - 5.0).abs() < 1e-9);
+		// prisoner's dilemma, Rock as defect: only pure defection is stable
+		let table = analysis::PayoffTable::new([[1.0, 5.0, 5.0], [0.0, 3.0, 3.0], [0.0, 3.0, 3.0]]);
+		assert_eq!(table.equilibrium(), ([1.0, 0.0, 0.0], 1.0));
+	}
+
+	#[test]
+	fn tournament() {
+		use tournament::*;
+		let mut t = Tournament::new(100)
+			.with_bot(Constant(Hand::Rock))
+			.with_bot(Cycle)
+			.with_bot(CopyLast(Hand::Paper))
+			.with_bot(FrequencyCounter)
+			.with_bot(Random::new(42));
+		let standings = t.run();
+		let text = standings.to_string();
+		let mut rows = text.lines();
+		let header = rows.next().unwrap();
+		assert!(header.starts_with("bot ") && header.ends_with("pts   w   d   l  score"));
+		assert_eq!(rows.count(), 5);
+		assert!(text.contains("\nfrequency-counter "));
+		assert_eq!(standings.0.len(), 5);
+		// every bot plays every other bot once
+		assert!(standings.0.iter().all(|s| s.wins + s.draws + s.losses == 4));
+		// seeded bots make the whole tournament reproducible
+		assert_eq!(t.run(), standings);
+
+		// adjacent seeds play differently
+		for seed in 0..8 {
+			let (mut a, mut b) = (Rng::new(seed), Rng::new(seed + 1));
+			let a: Vec<u64> = (0..4).map(|_| a.next_u64()).collect();
+			let b: Vec<u64> = (0..4).map(|_| b.next_u64()).collect();
+			assert_ne!(a, b);
+			let (mut a, mut b) = (Random::new(seed), Random::new(seed + 1));
+			let a: Vec<Hand> = (0..20).map(|_| a.play(&[])).collect();
+			let b: Vec<Hand> = (0..20).map(|_| b.play(&[])).collect();
+			assert_ne!(a, b);
+		}
+
+		let frequency = standings.0.iter().find(|s| s.name == "frequency-counter");
+		let constant = standings.0.iter().find(|s| s.name == "constant-Rock");
+		assert!(frequency.unwrap().points() > constant.unwrap().points());
+	}
 }
@@ -0,0 +1,255 @@
+use std::fmt::Display;
+
+use crate::{Hand, Round};
+
+/// A player for `Tournament` matches
+pub trait Bot {
+	fn name(&self) -> String;
+
+	/// Next hand, given the rounds played so far in this match as (own, opponent)
+	fn play(&mut self, history: &[(Hand, Hand)]) -> Hand;
+
+	/// Called before every match
+	fn reset(&mut self) {}
+}
+
+/// Always plays the same hand
+pub struct Constant(pub Hand);
+
+impl Bot for Constant {
+	fn name(&self) -> String {
+		format!("constant-{:?}", self.0)
+	}
+
+	fn play(&mut self, _history: &[(Hand, Hand)]) -> Hand {
+		self.0
+	}
+}
+
+/// Rock, Paper, Scissors, Rock, ...
+pub struct Cycle;
+
+impl Bot for Cycle {
+	fn name(&self) -> String {
+		"cycle".to_string()
+	}
+
+	fn play(&mut self, history: &[(Hand, Hand)]) -> Hand {
+		Hand::ALL[history.len() % 3]
+	}
+}
+
+/// Plays whatever the opponent played last round, `first` on the opening round
+pub struct CopyLast(pub Hand);
+
+impl Bot for CopyLast {
+	fn name(&self) -> String {
+		"copy-last".to_string()
+	}
+
+	fn play(&mut self, history: &[(Hand, Hand)]) -> Hand {
+		history.last().map_or(self.0, |&(_own, op)| op)
+	}
+}
+
+/// Beats the opponent's most frequent hand so far (ties go to `Hand::ALL` order)
+pub struct FrequencyCounter;
+
+impl Bot for FrequencyCounter {
+	fn name(&self) -> String {
+		"frequency-counter".to_string()
+	}
+
+	fn play(&mut self, history: &[(Hand, Hand)]) -> Hand {
+		let mut counts = [0usize; 3];
+		for &(_own, op) in history {
+			counts[op as usize - 1] += 1;
+		}
+		let mut most = Hand::Rock;
+		for hand in Hand::ALL {
+			if counts[hand as usize - 1] > counts[most as usize - 1] {
+				most = hand;
+			}
+		}
+		most.loses()
+	}
+}
+
+/// xorshift64* generator, enough to get reproducible "random" play
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+	pub fn new(seed: u64) -> Self {
+		// one splitmix64 step, so nearby seeds give unrelated states
+		let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+		z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+		z ^= z >> 31;
+		// state must never be 0
+		Rng(if z == 0 { 1 } else { z })
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		self.0 ^= self.0 >> 12;
+		self.0 ^= self.0 << 25;
+		self.0 ^= self.0 >> 27;
+		self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+	}
+
+	pub fn hand(&mut self) -> Hand {
+		Hand::ALL[(self.next_u64() % 3) as usize]
+	}
+}
+
+/// Uniformly random play, restarted from `seed` on every match
+pub struct Random {
+	seed: u64,
+	rng: Rng,
+}
+
+impl Random {
+	pub fn new(seed: u64) -> Self {
+		Random {
+			seed,
+			rng: Rng::new(seed),
+		}
+	}
+}
+
+impl Bot for Random {
+	fn name(&self) -> String {
+		format!("random-{}", self.seed)
+	}
+
+	fn play(&mut self, _history: &[(Hand, Hand)]) -> Hand {
+		self.rng.hand()
+	}
+
+	fn reset(&mut self) {
+		self.rng = Rng::new(self.seed);
+	}
+}
+
+/// Totals over all matches played by one bot
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Standing {
+	pub name: String,
+	pub wins: usize,
+	pub draws: usize,
+	pub losses: usize,
+	/// sum of `Hand::score` over every round played
+	pub score: usize,
+}
+
+impl Standing {
+	/// 3 for a match win, 1 for a draw
+	pub fn points(&self) -> usize {
+		self.wins * 3 + self.draws
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standings(pub Vec<Standing>);
+
+impl Display for Standings {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let width = self.0.iter().map(|s| s.name.len()).max().unwrap_or(0);
+		writeln!(f, "{:<width$}  pts   w   d   l  score", "bot")?;
+		for s in &self.0 {
+			writeln!(
+				f,
+				"{:<width$}  {:>3} {:>3} {:>3} {:>3} {:>6}",
+				s.name,
+				s.points(),
+				s.wins,
+				s.draws,
+				s.losses,
+				s.score
+			)?;
+		}
+		Ok(())
+	}
+}
+
+/// Round-robin between bots, every pair plays one match of `rounds` rounds.
+/// A match is won by winning more rounds than the opponent.
+pub struct Tournament {
+	rounds: usize,
+	bots: Vec<Box<dyn Bot>>,
+}
+
+impl Tournament {
+	pub fn new(rounds: usize) -> Self {
+		Tournament {
+			rounds,
+			bots: vec![],
+		}
+	}
+
+	pub fn with_bot(mut self, bot: impl Bot + 'static) -> Self {
+		self.bots.push(Box::new(bot));
+		self
+	}
+
+	/// Play one match between bots `a` and `b`, returns their (rounds won, score)
+	fn play_match(&mut self, a: usize, b: usize) -> ((usize, usize), (usize, usize)) {
+		let (mut history_a, mut history_b) = (vec![], vec![]);
+		let (mut won_a, mut won_b, mut score_a, mut score_b) = (0, 0, 0, 0);
+		self.bots[a].reset();
+		self.bots[b].reset();
+		for _ in 0..self.rounds {
+			let hand_a = self.bots[a].play(&history_a);
+			let hand_b = self.bots[b].play(&history_b);
+			match hand_a.vs(&hand_b) {
+				Round::Win => won_a += 1,
+				Round::Loss => won_b += 1,
+				Round::Draw => {}
+			}
+			score_a += hand_a.score(&hand_b);
+			score_b += hand_b.score(&hand_a);
+			history_a.push((hand_a, hand_b));
+			history_b.push((hand_b, hand_a));
+		}
+		((won_a, score_a), (won_b, score_b))
+	}
+
+	pub fn run(&mut self) -> Standings {
+		let mut standings: Vec<Standing> = self
+			.bots
+			.iter()
+			.map(|bot| Standing {
+				name: bot.name(),
+				..Default::default()
+			})
+			.collect();
+		for a in 0..self.bots.len() {
+			for b in a + 1..self.bots.len() {
+				let ((won_a, score_a), (won_b, score_b)) = self.play_match(a, b);
+				standings[a].score += score_a;
+				standings[b].score += score_b;
+				match won_a.cmp(&won_b) {
+					std::cmp::Ordering::Greater => {
+						standings[a].wins += 1;
+						standings[b].losses += 1;
+					}
+					std::cmp::Ordering::Less => {
+						standings[a].losses += 1;
+						standings[b].wins += 1;
+					}
+					std::cmp::Ordering::Equal => {
+						standings[a].draws += 1;
+						standings[b].draws += 1;
+					}
+				}
+			}
+		}
+		standings.sort_by(|x, y| {
+			y.points()
+				.cmp(&x.points())
+				.then(y.score.cmp(&x.score))
+				.then(x.name.cmp(&y.name))
+		});
+		Standings(standings)
+	}
+}
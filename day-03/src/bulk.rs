@@ -0,0 +1,125 @@
+//! Byte oriented rucksack processing straight off the input buffer.
+//!
+//! Every line is reduced to a `u64` mask with one table lookup per byte, and groups are then
+//! just `&` of masks. Only `a-zA-Z0-9` count as items here, any other byte is ignored, and an
+//! odd-length line is split with the extra item on the right instead of being an error.
+//! Every shared item is summed, so on puzzle input this matches `p1`/`p2`, and on input of
+//! even-length lines of `a-zA-Z0-9` it matches `shared_by_compartments`/`shared_by_groups`
+//! in `Mode::Any`.
+
+/// Bit of every byte's item priority, 0 for bytes that aren't items
+const BITS: [u64; 256] = {
+	let mut table = [0u64; 256];
+	let mut b = 0;
+	while b < 26 {
+		table[b'a' as usize + b] = 1 << (b + 1);
+		table[b'A' as usize + b] = 1 << (b + 27);
+		b += 1;
+	}
+	let mut d = 0;
+	while d < 10 {
+		table[b'0' as usize + d] = 1 << (d + 53);
+		d += 1;
+	}
+	table
+};
+
+fn mask(items: &[u8]) -> u64 {
+	// independent accumulators let the ORs run in parallel
+	let mut acc = [0u64; 4];
+	let mut chunks = items.chunks_exact(4);
+	for c in &mut chunks {
+		acc[0] |= BITS[c[0] as usize];
+		acc[1] |= BITS[c[1] as usize];
+		acc[2] |= BITS[c[2] as usize];
+		acc[3] |= BITS[c[3] as usize];
+	}
+	for &b in chunks.remainder() {
+		acc[0] |= BITS[b as usize];
+	}
+	acc[0] | acc[1] | acc[2] | acc[3]
+}
+
+/// Sum of the priorities of all items in the mask
+fn sum_bits(mut bits: u64) -> usize {
+	let mut total = 0;
+	while bits != 0 {
+		total += bits.trailing_zeros() as usize;
+		bits &= bits - 1;
+	}
+	total
+}
+
+/// Lines of the buffer, without `\r\n`/`\n`, skipping a trailing empty one
+fn lines(input: &[u8]) -> impl Iterator<Item = &[u8]> {
+	let input = input.strip_suffix(b"\n").unwrap_or(input);
+	input
+		.split(|&b| b == b'\n')
+		.map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+}
+
+/// Same as `p1`, over the raw input
+pub fn p1(input: &[u8]) -> usize {
+	lines(input)
+		.map(|line| {
+			let (left, right) = line.split_at(line.len() / 2);
+			sum_bits(mask(left) & mask(right))
+		})
+		.sum()
+}
+
+/// Same as `p2`, over the raw input
+pub fn p2(input: &[u8]) -> usize {
+	let mut total = 0;
+	let mut lines = lines(input);
+	while let Some(a) = lines.next() {
+		let mut overlap = mask(a);
+		for line in lines.by_ref().take(2) {
+			overlap &= mask(line);
+		}
+		total += sum_bits(overlap);
+	}
+	total
+}
+
+/// xorshift generator for reproducible random input
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+	fn new(seed: u64) -> Self {
+		// one splitmix64 step, so nearby seeds give unrelated states
+		let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+		z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+		z ^= z >> 31;
+		// state must never be 0
+		Rng(if z == 0 { 1 } else { z })
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+
+	/// Some number below `n`
+	fn below(&mut self, n: usize) -> usize {
+		(self.next_u64() % n as u64) as usize
+	}
+}
+
+/// `n` random rucksacks, each line `2 * half` items long
+pub fn generate(n: usize, half: usize, seed: u64) -> Vec<u8> {
+	const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+	let mut rng = Rng::new(seed);
+	let mut v = Vec::with_capacity(n * (2 * half + 1));
+	for _ in 0..n {
+		for _ in 0..2 * half {
+			v.push(ITEMS[rng.below(ITEMS.len())]);
+		}
+		v.push(b'\n');
+	}
+	v
+}
@@ -1,52 +1,182 @@
-pub fn item_as_bit(item: &char) -> u64 {
-	let sh = if item.is_ascii_lowercase() {
-		// a..z => 1..26
-		(*item as u64) - ('a' as u64) + 1
+use std::collections::BTreeSet;
+
+pub mod bulk;
+
+/// Default item priorities: a..z => 1..26, A..Z => 27..52, 0..9 => 53..62,
+/// any other char => 64 + its scalar value.
+pub fn priority(item: char) -> usize {
+	if item.is_ascii_lowercase() {
+		(item as usize) - ('a' as usize) + 1
 	} else if item.is_ascii_uppercase() {
-		//A..Z => 27..52
-		(*item as u64) - ('A' as u64) + 27
+		(item as usize) - ('A' as usize) + 27
+	} else if item.is_ascii_digit() {
+		(item as usize) - ('0' as usize) + 53
 	} else {
-		unreachable!("Can't item_as_bit({item})");
-	};
-	assert!((1..=52).contains(&sh));
+		64 + item as usize
+	}
+}
+
+/// Bit for an item in the `u64` fast path, panics if its priority doesn't fit
+pub fn item_as_bit(item: &char) -> u64 {
+	let sh = priority(*item);
+	assert!((1..64).contains(&sh), "Can't item_as_bit({item})");
 	1_u64 << sh
 }
 
-pub fn p1(lines: &Vec<String>) -> usize {
-	let mut total = 0;
-	for line in lines {
-		let (left, right) = line.split_at(line.len() / 2);
-		let (mut left_bits, mut right_bits) = (0u64, 0u64);
-		for (left, right) in left.chars().zip(right.chars()) {
-			left_bits |= item_as_bit(&left);
-			right_bits |= item_as_bit(&right);
+/// Set of item priorities: a `u64` bitset for priorities below 64,
+/// and a sparse set for anything above.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemSet {
+	bits: u64,
+	sparse: BTreeSet<usize>,
+}
+
+impl ItemSet {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Every item of `items`, prioritized with `prio`
+	pub fn from_items(items: &str, prio: impl Fn(char) -> usize) -> Self {
+		let mut set = Self::new();
+		for item in items.chars() {
+			set.insert(prio(item));
+		}
+		set
+	}
+
+	pub fn insert(&mut self, p: usize) {
+		if p < 64 {
+			self.bits |= 1 << p;
+		} else {
+			self.sparse.insert(p);
+		}
+	}
+
+	pub fn contains(&self, p: usize) -> bool {
+		if p < 64 {
+			self.bits & (1 << p) != 0
+		} else {
+			self.sparse.contains(&p)
 		}
-		let overlap: u64 = left_bits & right_bits;
-		assert!(overlap.count_ones() == 1);
-		let p = overlap.trailing_zeros();
-		total += p;
 	}
-	total.try_into().unwrap()
+
+	pub fn intersection(&self, other: &ItemSet) -> ItemSet {
+		ItemSet {
+			bits: self.bits & other.bits,
+			sparse: self.sparse.intersection(&other.sparse).copied().collect(),
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.bits.count_ones() as usize + self.sparse.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Priorities in ascending order
+	pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+		(0..64)
+			.filter(|p| self.bits & (1 << p) != 0)
+			.chain(self.sparse.iter().copied())
+	}
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OverlapError {
+	/// rucksack at this line can't be split into equal compartments
+	UnevenCompartments(usize),
+	/// number of lines isn't a multiple of the group size
+	UnevenGroups,
+	/// strict mode: group at this index doesn't share exactly one item
+	NotExactlyOne(usize, ItemSet),
+}
+
+/// How many items a group is expected to share
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+	/// exactly one, like the puzzle promises
+	Strict,
+	/// any number, including none
+	Any,
+}
+
+/// Items shared by every part of one group
+fn shared<'a>(
+	parts: impl Iterator<Item = &'a str>,
+	index: usize,
+	mode: Mode,
+	prio: impl Fn(char) -> usize + Copy,
+) -> Result<ItemSet, OverlapError> {
+	let mut overlap: Option<ItemSet> = None;
+	for part in parts {
+		let items = ItemSet::from_items(part, prio);
+		overlap = Some(match overlap {
+			Some(overlap) => overlap.intersection(&items),
+			None => items,
+		});
+	}
+	let overlap = overlap.unwrap_or_default();
+	if mode == Mode::Strict && overlap.len() != 1 {
+		return Err(OverlapError::NotExactlyOne(index, overlap));
+	}
+	Ok(overlap)
 }
 
-pub fn p2(lines: &mut Vec<String>) -> usize {
-	let mut total = 0;
-	assert!(lines.len() % 3 == 0);
-	for group in lines.chunks(3) {
-		let mut overlap = !0u64;
-		assert!(overlap.count_ones() == 64);
-		for line in group {
-			let mut bits = 0u64;
-			for item in line.chars() {
-				bits |= item_as_bit(&item);
-			}
-			overlap &= bits;
+/// For every rucksack, the items found in all of its `n` compartments
+pub fn shared_by_compartments(
+	lines: &[String],
+	n: usize,
+	mode: Mode,
+	prio: impl Fn(char) -> usize + Copy,
+) -> Result<Vec<ItemSet>, OverlapError> {
+	assert!(n > 0);
+	let mut v = vec![];
+	for (i, line) in lines.iter().enumerate() {
+		let items: Vec<char> = line.chars().collect();
+		if !items.len().is_multiple_of(n) {
+			return Err(OverlapError::UnevenCompartments(i));
 		}
-		assert!(overlap.count_ones() == 1);
-		let p = overlap.trailing_zeros();
-		total += p;
+		let parts: Vec<String> = items
+			.chunks((items.len() / n).max(1))
+			.map(|c| c.iter().collect())
+			.collect();
+		v.push(shared(parts.iter().map(|p| p.as_str()), i, mode, prio)?);
 	}
-	total.try_into().unwrap()
+	Ok(v)
+}
+
+/// For every group of `size` consecutive rucksacks, the items found in all of them
+pub fn shared_by_groups(
+	lines: &[String],
+	size: usize,
+	mode: Mode,
+	prio: impl Fn(char) -> usize + Copy,
+) -> Result<Vec<ItemSet>, OverlapError> {
+	assert!(size > 0);
+	if mode == Mode::Strict && !lines.len().is_multiple_of(size) {
+		return Err(OverlapError::UnevenGroups);
+	}
+	lines
+		.chunks(size)
+		.enumerate()
+		.map(|(i, group)| shared(group.iter().map(|l| l.as_str()), i, mode, prio))
+		.collect()
+}
+
+/// Sum of the priorities of every item in every set
+pub fn sum_priorities(sets: &[ItemSet]) -> usize {
+	sets.iter().flat_map(|set| set.iter()).sum()
+}
+
+pub fn p1(lines: &[String]) -> usize {
+	sum_priorities(&shared_by_compartments(lines, 2, Mode::Strict, priority).unwrap())
+}
+
+pub fn p2(lines: &[String]) -> usize {
+	sum_priorities(&shared_by_groups(lines, 3, Mode::Strict, priority).unwrap())
 }
 
 #[cfg(test)]
@@ -79,22 +209,111 @@ mod tests {
 	#[test]
 	fn example2() {
 		let fd = std::fs::File::open("example.txt").unwrap();
-		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+		let lines: Vec<String> = std::io::BufReader::new(fd)
 			.lines()
 			.map(|l| l.unwrap())
 			.collect();
-		let r = p2(&mut lines);
+		let r = p2(&lines);
 		assert_eq!(r, 70);
 	}
 
 	#[test]
 	fn part2() {
 		let fd = std::fs::File::open("input.txt").unwrap();
-		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+		let lines: Vec<String> = std::io::BufReader::new(fd)
 			.lines()
 			.map(|l| l.unwrap())
 			.collect();
-		let r = p2(&mut lines);
+		let r = p2(&lines);
 		assert_eq!(r, 2604);
 	}
+
+	#[test]
+	fn items() {
+		assert_eq!(priority('a'), 1);
+		assert_eq!(priority('Z'), 52);
+		assert_eq!(priority('9'), 62);
+		assert_eq!(item_as_bit(&'0'), 1 << 53);
+
+		let left = ItemSet::from_items("ab1é€", priority);
+		let right = ItemSet::from_items("b€x1", priority);
+		let common = left.intersection(&right);
+		assert_eq!(
+			common.iter().collect::<Vec<_>>(),
+			[priority('b'), priority('1'), priority('€')]
+		);
+		assert!(!common.contains(priority('é')));
+
+		// custom priorities: scalar value
+		let common = ItemSet::from_items("λμν", |c| c as usize)
+			.intersection(&ItemSet::from_items("νξ", |c| c as usize));
+		assert_eq!(common.iter().collect::<Vec<_>>(), ['ν' as usize]);
+	}
+
+	#[test]
+	fn groups() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		// a group of 2 shares more than a group of 3
+		let shared = shared_by_groups(&lines, 2, Mode::Any, priority).unwrap();
+		assert_eq!(shared.len(), 3);
+		assert!(shared.iter().any(|s| s.len() > 1));
+		assert!(matches!(
+			shared_by_groups(&lines, 2, Mode::Strict, priority),
+			Err(OverlapError::NotExactlyOne(..))
+		));
+		assert_eq!(
+			shared_by_groups(&lines, 4, Mode::Strict, priority),
+			Err(OverlapError::UnevenGroups)
+		);
+		assert_eq!(
+			shared_by_groups(&lines, 4, Mode::Any, priority)
+				.unwrap()
+				.len(),
+			2
+		);
+
+		let lines = vec!["abcabc".to_string(), "aabbc".to_string()];
+		let shared = shared_by_compartments(&lines[..1], 3, Mode::Any, priority).unwrap();
+		assert!(shared[0].is_empty());
+		assert_eq!(
+			shared_by_compartments(&lines, 2, Mode::Any, priority),
+			Err(OverlapError::UnevenCompartments(1))
+		);
+	}
+
+	#[test]
+	fn bulk() {
+		let input = std::fs::read("input.txt").unwrap();
+		let lines: Vec<String> = input.lines().map(|l| l.unwrap()).collect();
+		assert_eq!(bulk::p1(&input), p1(&lines));
+		assert_eq!(bulk::p2(&input), p2(&lines));
+
+		let input = bulk::generate(3000, 12, 7);
+		assert_ne!(input, bulk::generate(3000, 12, 6));
+		assert!(input.iter().any(u8::is_ascii_digit));
+		assert_eq!(bulk::p1(b"1a1a\n"), 55);
+		let lines: Vec<String> = input.lines().map(|l| l.unwrap()).collect();
+		let shared = shared_by_compartments(&lines, 2, Mode::Any, priority).unwrap();
+		assert_eq!(bulk::p1(&input), sum_priorities(&shared));
+		let shared = shared_by_groups(&lines, 3, Mode::Any, priority).unwrap();
+		assert_eq!(bulk::p2(&input), sum_priorities(&shared));
+	}
+
+	#[ignore]
+	#[test]
+	fn bulk_throughput() {
+		const N: usize = 3_000_000;
+		let input = bulk::generate(N, 16, 1);
+		let now = std::time::Instant::now();
+		let r = bulk::p1(&input) + bulk::p2(&input);
+		let secs = now.elapsed().as_secs_f64();
+		println!(
+			"{N} rucksacks in {secs:.3}s ({:.0}/s), {r}",
+			N as f64 / secs
+		);
+	}
 }
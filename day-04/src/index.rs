@@ -0,0 +1,130 @@
+use crate::{parse_group, Interval};
+
+/// One elf's assignment, and where it came from in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Assignment {
+	pub line: usize,
+	/// position in the line, 0 for the left elf of a pair
+	pub elf: usize,
+	pub interval: Interval,
+}
+
+/// Static interval tree over every assignment.
+///
+/// Assignments are sorted by start and the tree is implicit: the root of `lo..hi` is the middle
+/// element, its children the middles of both halves. Every node keeps the largest end in its
+/// subtree, so whole subtrees that end before the query are skipped.
+#[derive(Debug, Clone)]
+pub struct IntervalIndex {
+	items: Vec<Assignment>,
+	max_end: Vec<u64>,
+}
+
+impl IntervalIndex {
+	pub fn new(mut items: Vec<Assignment>) -> Self {
+		items.sort_by_key(|a| (a.interval.start, a.interval.end, a.line, a.elf));
+		let mut index = IntervalIndex {
+			max_end: vec![0; items.len()],
+			items,
+		};
+		index.build(0, index.items.len());
+		index
+	}
+
+	/// Index every assignment of every line (any number per line)
+	pub fn from_lines(lines: &[String]) -> Option<Self> {
+		let mut items = vec![];
+		for (line, s) in lines.iter().enumerate() {
+			for (elf, interval) in parse_group(s)?.into_iter().enumerate() {
+				items.push(Assignment {
+					line,
+					elf,
+					interval,
+				});
+			}
+		}
+		Some(Self::new(items))
+	}
+
+	fn build(&mut self, lo: usize, hi: usize) -> u64 {
+		if lo >= hi {
+			return 0;
+		}
+		let mid = lo + (hi - lo) / 2;
+		let left = self.build(lo, mid);
+		let right = self.build(mid + 1, hi);
+		self.max_end[mid] = self.items[mid].interval.end.max(left).max(right);
+		self.max_end[mid]
+	}
+
+	pub fn len(&self) -> usize {
+		self.items.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.items.is_empty()
+	}
+
+	/// Assignments sharing at least one section with `q`
+	pub fn overlapping(&self, q: &Interval) -> Vec<&Assignment> {
+		let mut v = vec![];
+		self.visit(0, self.items.len(), q.start, q.end, &mut |a| {
+			if a.interval.overlaps(q) {
+				v.push(a);
+			}
+		});
+		v
+	}
+
+	/// Assignments that fully contain `q`
+	pub fn containing(&self, q: &Interval) -> Vec<&Assignment> {
+		let mut v = vec![];
+		self.visit(0, self.items.len(), q.end, q.start, &mut |a| {
+			if a.interval.contains(q) {
+				v.push(a);
+			}
+		});
+		v
+	}
+
+	/// Every (outer, inner) pair where one assignment fully contains another, across all lines.
+	/// Identical assignments contain each other, so they show up both ways.
+	pub fn containments(&self) -> Vec<(&Assignment, &Assignment)> {
+		let mut v = vec![];
+		for inner in &self.items {
+			for outer in self.containing(&inner.interval) {
+				if outer != inner {
+					v.push((outer, inner));
+				}
+			}
+		}
+		v
+	}
+
+	/// Call `f` on every assignment with `end >= min_end` and `start <= max_start`
+	/// (a superset of what the callers want, they filter the rest)
+	fn visit<'a>(
+		&'a self,
+		lo: usize,
+		hi: usize,
+		min_end: u64,
+		max_start: u64,
+		f: &mut impl FnMut(&'a Assignment),
+	) {
+		if lo >= hi {
+			return;
+		}
+		let mid = lo + (hi - lo) / 2;
+		if self.max_end[mid] < min_end {
+			return;
+		}
+		self.visit(lo, mid, min_end, max_start, f);
+		let a = &self.items[mid];
+		if a.interval.start > max_start {
+			// everything right of mid starts even later
+			return;
+		}
+		f(a);
+		self.visit(mid + 1, hi, min_end, max_start, f);
+	}
+}
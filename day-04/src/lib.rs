@@ -1,39 +1,212 @@
+pub mod index;
+
+/// Inclusive range of sections `start..=end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval {
+	pub start: u64,
+	pub end: u64,
+}
+
+impl Interval {
+	/// Bounds may come in either order
+	pub fn new(a: u64, b: u64) -> Self {
+		Interval {
+			start: a.min(b),
+			end: a.max(b),
+		}
+	}
+
+	/// Parse `"2-4"`
+	pub fn parse(s: &str) -> Option<Self> {
+		let (left, right) = s.trim().split_once('-')?;
+		Some(Interval::new(left.parse().ok()?, right.parse().ok()?))
+	}
+
+	/// Saturates for `0..=u64::MAX`
+	pub fn len(&self) -> u64 {
+		(self.end - self.start).saturating_add(1)
+	}
+
+	/// Always false, an interval holds at least one section
+	pub fn is_empty(&self) -> bool {
+		false
+	}
+
+	/// Fully contains other
+	pub fn contains(&self, other: &Interval) -> bool {
+		self.start <= other.start && other.end <= self.end
+	}
+
+	pub fn overlaps(&self, other: &Interval) -> bool {
+		self.start <= other.end && other.start <= self.end
+	}
+
+	pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+		if self.overlaps(other) {
+			Some(Interval::new(
+				self.start.max(other.start),
+				self.end.min(other.end),
+			))
+		} else {
+			None
+		}
+	}
+
+	/// Merged interval, if the two overlap or touch
+	pub fn union(&self, other: &Interval) -> Option<Interval> {
+		if self.start <= other.end.saturating_add(1) && other.start <= self.end.saturating_add(1) {
+			Some(Interval::new(
+				self.start.min(other.start),
+				self.end.max(other.end),
+			))
+		} else {
+			None
+		}
+	}
+
+	/// Sections as bits (section 1 => bit 0), when every section fits in a `u128`
+	pub fn as_bits(&self) -> Option<u128> {
+		if self.start < 1 || self.end > 128 {
+			return None;
+		}
+		let len = self.len() as u32;
+		let ones = if len == 128 { !0 } else { (1u128 << len) - 1 };
+		Some(ones << (self.start - 1))
+	}
+}
+
 pub fn section_as_bits(s: &str) -> u128 {
-	let (left, right) = s.split_once('-').unwrap();
-	let (mut left, mut right) = (
-		left.parse::<u128>().unwrap(),
-		right.parse::<u128>().unwrap(),
-	);
-	if left > right {
-		(left, right) = (right, left);
-	}
-	assert!((1 <= left) && (right <= 99));
-	let mut bits: u128 = 0;
-	for sh in left..=right {
-		bits |= 1 << (sh - 1);
-	}
-	bits
+	let interval = Interval::parse(s).unwrap();
+	assert!((1 <= interval.start) && (interval.end <= 99));
+	interval.as_bits().unwrap()
+}
+
+/// Parse `"2-4,6-8"`
+pub fn parse_pair(line: &str) -> Option<(Interval, Interval)> {
+	let (left, right) = line.split_once(',')?;
+	Some((Interval::parse(left)?, Interval::parse(right)?))
 }
 
-pub fn p1(lines: &Vec<String>) -> usize {
+/// Parse any number of comma separated assignments, `"2-4,6-8,1-3"`
+pub fn parse_group(line: &str) -> Option<Vec<Interval>> {
+	line.split(',').map(Interval::parse).collect()
+}
+
+/// How a group of elves covers its sections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+	/// sections nobody covers
+	pub uncovered: Vec<Interval>,
+	/// per elf, how many sections only that elf covers
+	pub unique: Vec<u64>,
+	/// most elves assigned to the same section
+	pub max_depth: usize,
+	/// where `max_depth` is reached
+	pub deepest: Vec<Interval>,
+}
+
+impl Coverage {
+	/// Coverage over the span of the group, from its first to its last section
+	pub fn new(elves: &[Interval]) -> Self {
+		let start = elves.iter().map(|e| e.start).min().unwrap_or(0);
+		let end = elves.iter().map(|e| e.end).max().unwrap_or(0);
+		Self::within(elves, Interval::new(start, end))
+	}
+
+	/// Coverage of the sections in `bounds`, with a sweep over the assignment endpoints
+	pub fn within(elves: &[Interval], bounds: Interval) -> Self {
+		// (position, elf entering or leaving); positions are u128 so `end + 1` can't overflow
+		let mut events: Vec<(u128, bool, usize)> = vec![];
+		for (i, elf) in elves.iter().enumerate() {
+			events.push((elf.start as u128, true, i));
+			events.push((elf.end as u128 + 1, false, i));
+		}
+		events.sort_unstable();
+
+		let mut coverage = Coverage {
+			uncovered: vec![],
+			unique: vec![0; elves.len()],
+			max_depth: 0,
+			deepest: vec![],
+		};
+		let (lo, hi) = (bounds.start as u128, bounds.end as u128 + 1);
+		// with exactly one elf active, `active_sum` is that elf
+		let (mut depth, mut active_sum) = (0usize, 0usize);
+		let mut prev = lo;
+		let mut events = events.into_iter().peekable();
+		loop {
+			let pos = events.peek().map_or(hi, |e| e.0.clamp(lo, hi));
+			if pos > prev {
+				coverage.segment(prev as u64, (pos - 1) as u64, depth, active_sum);
+				prev = pos;
+			}
+			let Some((_, enter, i)) = events.next() else {
+				break;
+			};
+			if enter {
+				depth += 1;
+				active_sum += i;
+			} else {
+				depth -= 1;
+				active_sum -= i;
+			}
+		}
+		coverage
+	}
+
+	/// Account for sections `start..=end`, all covered by `depth` elves
+	fn segment(&mut self, start: u64, end: u64, depth: usize, active_sum: usize) {
+		let interval = Interval::new(start, end);
+		match depth {
+			0 => push_merged(&mut self.uncovered, interval),
+			1 => self.unique[active_sum] += interval.len(),
+			_ => {}
+		}
+		if depth > self.max_depth {
+			self.max_depth = depth;
+			self.deepest.clear();
+		}
+		if depth == self.max_depth {
+			push_merged(&mut self.deepest, interval);
+		}
+	}
+}
+
+/// Push, merging with the last interval if they touch
+fn push_merged(v: &mut Vec<Interval>, interval: Interval) {
+	if let Some(merged) = v.last().and_then(|last| last.union(&interval)) {
+		*v.last_mut().unwrap() = merged;
+	} else {
+		v.push(interval);
+	}
+}
+
+/// Either fully contains the other, through the bitset when both fit
+fn fully_contains(left: &Interval, right: &Interval) -> bool {
+	if let (Some(left_bits), Some(right_bits)) = (left.as_bits(), right.as_bits()) {
+		let overlap = left_bits & right_bits;
+		(overlap == left_bits) || (overlap == right_bits)
+	} else {
+		left.contains(right) || right.contains(left)
+	}
+}
+
+pub fn p1(lines: &[String]) -> usize {
 	let mut total = 0;
 	for line in lines {
-		let (left, right) = line.split_once(',').unwrap();
-		let (left_bits, right_bits) = (section_as_bits(left), section_as_bits(right));
-		let overlap = left_bits & right_bits;
-		if (overlap == left_bits) || (overlap == right_bits) {
+		let (left, right) = parse_pair(line).unwrap();
+		if fully_contains(&left, &right) {
 			total += 1;
 		}
 	}
 	total
 }
 
-pub fn p2(lines: &Vec<String>) -> usize {
+pub fn p2(lines: &[String]) -> usize {
 	let mut total = 0;
 	for line in lines {
-		let (left, right) = line.split_once(',').unwrap();
-		let (left_bits, right_bits) = (section_as_bits(left), section_as_bits(right));
-		if (left_bits & right_bits) != 0 {
+		let (left, right) = parse_pair(line).unwrap();
+		if left.overlaps(&right) {
 			total += 1;
 		}
 	}
@@ -70,22 +243,118 @@ mod tests {
 	#[test]
 	fn example2() {
 		let fd = std::fs::File::open("example.txt").unwrap();
-		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+		let lines: Vec<String> = std::io::BufReader::new(fd)
 			.lines()
 			.map(|l| l.unwrap())
 			.collect();
-		let r = p2(&mut lines);
+		let r = p2(&lines);
 		assert_eq!(r, 4);
 	}
 
 	#[test]
 	fn part2() {
 		let fd = std::fs::File::open("input.txt").unwrap();
-		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+		let lines: Vec<String> = std::io::BufReader::new(fd)
 			.lines()
 			.map(|l| l.unwrap())
 			.collect();
-		let r = p2(&mut lines);
+		let r = p2(&lines);
 		assert_eq!(r, 833);
 	}
+
+	#[test]
+	fn intervals() {
+		let a = Interval::new(2, 8);
+		let b = Interval::new(7, 3);
+		assert_eq!(b, Interval { start: 3, end: 7 });
+		assert!(a.contains(&b) && !b.contains(&a));
+		assert_eq!(a.intersection(&b), Some(b));
+		assert_eq!(a.len(), 7);
+
+		let big = Interval::parse("100-5000000000").unwrap();
+		let small = Interval::parse("4999999999-6000000000").unwrap();
+		assert!(big.overlaps(&small));
+		assert_eq!(big.as_bits(), None);
+		assert_eq!(big.union(&small), Some(Interval::new(100, 6_000_000_000)));
+		assert_eq!(
+			Interval::new(1, 2).union(&Interval::new(3, 4)),
+			Some(Interval::new(1, 4))
+		);
+		assert_eq!(Interval::new(1, 2).union(&Interval::new(4, 4)), None);
+		assert_eq!(Interval::new(1, 2).intersection(&Interval::new(4, 4)), None);
+		assert_eq!(section_as_bits("2-4"), 0b1110);
+
+		let lines = vec!["100-200,150-160".to_string(), "1-2,300-400".to_string()];
+		assert_eq!((p1(&lines), p2(&lines)), (1, 1));
+	}
+
+	#[test]
+	fn coverage() {
+		let elves = parse_group("2-4,6-8,3-7,12-12").unwrap();
+		let c = Coverage::new(&elves);
+		assert_eq!(c.uncovered, [Interval::new(9, 11)]);
+		assert_eq!(c.unique, [1, 1, 1, 1]);
+		assert_eq!(c.max_depth, 2);
+		assert_eq!(c.deepest, [Interval::new(3, 4), Interval::new(6, 7)]);
+
+		let c = Coverage::within(&elves, Interval::new(1, 5));
+		assert_eq!(c.uncovered, [Interval::new(1, 1)]);
+		assert_eq!(c.unique, [1, 0, 1, 0]);
+
+		let c = Coverage::new(&parse_group("0-18446744073709551615").unwrap());
+		assert_eq!((c.max_depth, c.unique[0]), (1, u64::MAX));
+	}
+
+	#[test]
+	fn index() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let index = index::IntervalIndex::from_lines(&lines).unwrap();
+		assert_eq!(index.len(), 12);
+
+		// within a pair, the same answer as p1
+		let mut pairs: Vec<usize> = index
+			.containments()
+			.iter()
+			.filter(|(outer, inner)| outer.line == inner.line)
+			.map(|(outer, _)| outer.line)
+			.collect();
+		pairs.sort();
+		pairs.dedup();
+		assert_eq!(pairs.len(), p1(&lines));
+
+		// 2-8 contains assignments from other lines too
+		let inner: Vec<_> = index
+			.containments()
+			.iter()
+			.filter(|(outer, _)| outer.line == 3 && outer.elf == 0)
+			.map(|(_, inner)| inner.interval)
+			.collect();
+		assert!(inner.contains(&Interval::new(2, 4)));
+		assert!(inner.contains(&Interval::new(6, 6)));
+
+		let found = index.overlapping(&Interval::new(9, 20));
+		assert_eq!(found.len(), 1);
+		assert_eq!((found[0].line, found[0].elf), (2, 1));
+		assert_eq!(index.containing(&Interval::new(1, 9)).len(), 0);
+
+		let fd = std::fs::File::open("input.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let index = index::IntervalIndex::from_lines(&lines).unwrap();
+		let all: Vec<Interval> = lines.iter().flat_map(|l| parse_group(l).unwrap()).collect();
+		for q in [
+			Interval::new(1, 1),
+			Interval::new(40, 45),
+			Interval::new(98, 200),
+		] {
+			let brute = all.iter().filter(|a| a.overlaps(&q)).count();
+			assert_eq!(index.overlapping(&q).len(), brute);
+		}
+	}
 }
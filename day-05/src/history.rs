@@ -0,0 +1,182 @@
+use crate::{Crane, Harbour, Move, MoveError};
+
+/// Top of one stack before and after a move, everything below `keep` was left alone
+/// and `before`/`after` hold only what moved
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Change {
+	stack: usize,
+	keep: usize,
+	before: Vec<String>,
+	after: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+	m: Move,
+	changes: Vec<Change>,
+}
+
+/// Harbour that remembers every applied move, so they can be undone and redone.
+///
+/// Steps are numbered like moves: step `k` is the state after the `k`th move, step 0 the
+/// drawing. Queries only look at the moves up to the current step.
+#[derive(Debug, Clone)]
+pub struct History {
+	harbour: Harbour,
+	steps: Vec<Step>,
+	/// number of steps currently applied
+	cursor: usize,
+}
+
+impl History {
+	pub fn new(harbour: Harbour) -> Self {
+		History {
+			harbour,
+			steps: vec![],
+			cursor: 0,
+		}
+	}
+
+	pub fn harbour(&self) -> &Harbour {
+		&self.harbour
+	}
+
+	/// Current step
+	pub fn position(&self) -> usize {
+		self.cursor
+	}
+
+	/// Number of recorded moves, including undone ones
+	pub fn len(&self) -> usize {
+		self.steps.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.steps.is_empty()
+	}
+
+	/// Moves up to the current step
+	pub fn moves(&self) -> impl Iterator<Item = &Move> {
+		self.steps[..self.cursor].iter().map(|s| &s.m)
+	}
+
+	/// Do a move with `crane` and record it, forgetting any undone moves
+	pub fn apply(&mut self, crane: &dyn Crane, m: &Move) -> Result<(), MoveError> {
+		self.harbour.check(m)?;
+		let mut stacks = vec![m.src];
+		if m.dst != m.src {
+			stacks.push(m.dst);
+		}
+		let mut changes: Vec<Change> = stacks
+			.into_iter()
+			.map(|stack| {
+				let keep = self.harbour.sections[stack].len().saturating_sub(m.count);
+				Change {
+					stack,
+					keep,
+					before: self.harbour.sections[stack][keep..].to_vec(),
+					after: vec![],
+				}
+			})
+			.collect();
+		crane.lift(&mut self.harbour, m)?;
+		for change in changes.iter_mut() {
+			let after = &self.harbour.sections[change.stack][change.keep..];
+			// shrink to the crates that actually moved
+			let same = change
+				.before
+				.iter()
+				.zip(after)
+				.take_while(|(a, b)| a == b)
+				.count();
+			change.keep += same;
+			change.before.drain(..same);
+			change.after = after[same..].to_vec();
+		}
+		self.steps.truncate(self.cursor);
+		self.steps.push(Step { m: *m, changes });
+		self.cursor += 1;
+		Ok(())
+	}
+
+	/// Go back one step, false if already at the start
+	pub fn undo(&mut self) -> bool {
+		if self.cursor == 0 {
+			return false;
+		}
+		self.cursor -= 1;
+		for change in &self.steps[self.cursor].changes {
+			let stack = &mut self.harbour.sections[change.stack];
+			stack.truncate(change.keep);
+			stack.extend(change.before.iter().cloned());
+		}
+		true
+	}
+
+	/// Go forward one undone step, false if there is none
+	pub fn redo(&mut self) -> bool {
+		if self.cursor == self.steps.len() {
+			return false;
+		}
+		for change in &self.steps[self.cursor].changes {
+			let stack = &mut self.harbour.sections[change.stack];
+			stack.truncate(change.keep);
+			stack.extend(change.after.iter().cloned());
+		}
+		self.cursor += 1;
+		true
+	}
+
+	/// Jump to the state after move `k`, false if it was never recorded
+	pub fn goto(&mut self, k: usize) -> bool {
+		if k > self.steps.len() {
+			return false;
+		}
+		while self.cursor > k {
+			self.undo();
+		}
+		while self.cursor < k {
+			self.redo();
+		}
+		true
+	}
+
+	/// Last move that lifted or put down a crate named `name`
+	pub fn last_touched(&self, name: &str) -> Option<usize> {
+		self.steps[..self.cursor]
+			.iter()
+			.rposition(|step| {
+				step.changes
+					.iter()
+					.flat_map(|c| c.before.iter().chain(&c.after))
+					.any(|c| c == name)
+			})
+			.map(|i| i + 1)
+	}
+
+	/// First step at which stack (0 based) is empty
+	pub fn first_empty(&self, stack: usize) -> Option<usize> {
+		let steps = &self.steps[..self.cursor];
+		let changes = || {
+			steps
+				.iter()
+				.flat_map(|s| &s.changes)
+				.filter(|c| c.stack == stack)
+		};
+		let initial = match changes().next() {
+			Some(c) => c.keep + c.before.len(),
+			None => self.harbour.sections[stack].len(),
+		};
+		if initial == 0 {
+			return Some(0);
+		}
+		steps
+			.iter()
+			.position(|step| {
+				step.changes
+					.iter()
+					.any(|c| c.stack == stack && c.keep + c.after.len() == 0)
+			})
+			.map(|i| i + 1)
+	}
+}
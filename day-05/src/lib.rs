@@ -1,18 +1,94 @@
 use std::fmt::Display;
+use std::str::FromStr;
 
-#[derive(Debug)]
-struct Harbour {
-	sections: Vec<Vec<char>>,
+pub mod history;
+pub mod planner;
+pub mod rope;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+	/// drawing has no row of stack labels
+	NoLabelRow,
+	/// crate at (line, char) isn't above any label
+	StrayCrate(usize, usize),
+	/// `[` without a closing `]` at (line, char)
+	UnclosedCrate(usize, usize),
+	BadMove(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MoveError {
+	NoSuchStack(usize),
+	NotEnoughCrates {
+		stack: usize,
+		have: usize,
+		want: usize,
+	},
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Harbour {
+	/// label row entries, in order
+	labels: Vec<String>,
+	/// stacks of crates, bottom first
+	sections: Vec<Vec<String>>,
 }
 
 impl Harbour {
-	fn new(n: usize) -> Harbour {
+	fn new(labels: Vec<String>) -> Harbour {
 		Harbour {
-			sections: vec![Vec::new(); n],
+			sections: vec![Vec::new(); labels.len()],
+			labels,
+		}
+	}
+
+	/// Parse the drawing and the moves below it.
+	///
+	/// Columns are located from the label row, so trailing spaces may be stripped, there may
+	/// be any number of stacks and crates may have labels of any length: a crate belongs to
+	/// the stack whose label lies under its brackets.
+	pub fn parse(lines: &[String]) -> Result<(Harbour, Vec<Move>), ParseError> {
+		let split = lines
+			.iter()
+			.position(|l| l.trim().is_empty() || l.starts_with("move"))
+			.unwrap_or(lines.len());
+		let (drawing, moves) = lines.split_at(split);
+		let (label_row, crate_rows) = drawing.split_last().ok_or(ParseError::NoLabelRow)?;
+		let labels = tokens(label_row);
+		if labels.is_empty() || label_row.contains('[') {
+			return Err(ParseError::NoLabelRow);
+		}
+		let mut harbour = Harbour::new(labels.iter().map(|(_, _, l)| l.to_string()).collect());
+		for (y, row) in crate_rows.iter().enumerate() {
+			let chars: Vec<char> = row.chars().collect();
+			let mut x = 0;
+			while x < chars.len() {
+				if chars[x] != '[' {
+					x += 1;
+					continue;
+				}
+				let close = (x + 1..chars.len())
+					.find(|&i| chars[i] == ']')
+					.ok_or(ParseError::UnclosedCrate(y, x))?;
+				let section = labels
+					.iter()
+					.position(|&(start, end, _)| start <= close && x <= end)
+					.ok_or(ParseError::StrayCrate(y, x))?;
+				let name: String = chars[x + 1..close].iter().collect();
+				harbour.push_container(section, name);
+				x = close + 1;
+			}
 		}
+		harbour.rev_stacks();
+		let moves = moves
+			.iter()
+			.filter(|l| !l.trim().is_empty())
+			.map(|l| l.parse::<Move>())
+			.collect::<Result<Vec<_>, _>>()?;
+		Ok((harbour, moves))
 	}
 
-	fn push_container(&mut self, section: usize, c: char) {
+	fn push_container(&mut self, section: usize, c: String) {
 		self.sections[section].push(c);
 	}
 
@@ -22,121 +98,280 @@ impl Harbour {
 		}
 	}
 
-	/// with old boring 9000 crane...
-	fn do_move(&mut self, m: &Move) {
-		for _ in 0..m.count {
-			let c = self.sections[m.src].pop().unwrap();
-			self.sections[m.dst].push(c);
+	/// Check that the move can be done without touching the stacks
+	pub fn check(&self, m: &Move) -> Result<(), MoveError> {
+		let n = self.sections.len();
+		for stack in [m.src, m.dst] {
+			if stack >= n {
+				return Err(MoveError::NoSuchStack(stack + 1));
+			}
+		}
+		let have = self.sections[m.src].len();
+		if have < m.count {
+			return Err(MoveError::NotEnoughCrates {
+				stack: m.src + 1,
+				have,
+				want: m.count,
+			});
 		}
+		Ok(())
 	}
 
-	/// with crane 9001 can move multiple container in one move
-	fn do_move_crane(&mut self, m: &Move) {
-		let mut v: Vec<char> = vec![];
-		for _ in 0..m.count {
-			let c = self.sections[m.src].pop().unwrap();
-			v.push(c);
-		}
-		for _ in 0..m.count {
-			let c = v.pop().unwrap();
-			self.sections[m.dst].push(c);
-		}
+	/// Remove the top `count` crates of a stack, bottom first
+	fn take(&mut self, stack: usize, count: usize) -> Vec<String> {
+		let at = self.sections[stack].len() - count;
+		self.sections[stack].split_off(at)
 	}
 
-	fn top_as_str(&self) -> String {
+	fn put(&mut self, stack: usize, crates: Vec<String>) {
+		self.sections[stack].extend(crates);
+	}
+
+	pub fn top_as_str(&self) -> String {
 		let mut s = "".to_string();
 		for stack in &self.sections {
-			if let Some(&c) = &stack.last() {
-				s.push(c);
+			if let Some(c) = stack.last() {
+				s.push_str(c);
 			}
 		}
 		s
 	}
 }
 
+/// Whitespace separated words of a line, as (first char, last char, word)
+fn tokens(line: &str) -> Vec<(usize, usize, &str)> {
+	let mut v = vec![];
+	let mut start: Option<(usize, usize)> = None;
+	let mut chars = line.char_indices().enumerate().peekable();
+	while let Some((x, (b, c))) = chars.next() {
+		if c.is_whitespace() {
+			continue;
+		}
+		let (x0, b0) = *start.get_or_insert((x, b));
+		let ends = chars
+			.peek()
+			.is_none_or(|(_, (_, next))| next.is_whitespace());
+		if ends {
+			v.push((x0, x, &line[b0..b + c.len_utf8()]));
+			start = None;
+		}
+	}
+	v
+}
+
+/// The puzzle drawing: crates in bracketed columns above the label row, lines padded to the
+/// full width. Every line ends with a newline, so the moves can simply follow a blank line.
 impl Display for Harbour {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		let n = self.sections.len();
-		write!(f, "Harbour with {n} sections:")?;
-		for (i, stack) in self.sections.iter().enumerate() {
-			writeln!(f, "{i} = [")?;
-			for &c in stack.iter() {
-				write!(f, "{c}")?;
+		let widths: Vec<usize> = self
+			.labels
+			.iter()
+			.zip(&self.sections)
+			.map(|(label, stack)| {
+				let widest = stack.iter().map(|c| c.chars().count() + 2).max();
+				widest.unwrap_or(0).max(label.chars().count())
+			})
+			.collect();
+		let height = self.sections.iter().map(|s| s.len()).max().unwrap_or(0);
+		for y in (0..height).rev() {
+			for (x, stack) in self.sections.iter().enumerate() {
+				if x > 0 {
+					write!(f, " ")?;
+				}
+				let c = stack.get(y).map(|c| format!("[{c}]")).unwrap_or_default();
+				write!(f, "{c:<w$}", w = widths[x])?;
 			}
-			write!(f, "]")?;
+			writeln!(f)?;
 		}
-		Ok(())
+		for (x, label) in self.labels.iter().enumerate() {
+			if x > 0 {
+				write!(f, " ")?;
+			}
+			write!(f, "{label:^w$}", w = widths[x])?;
+		}
+		writeln!(f)
 	}
 }
 
-struct Move {
-	count: usize,
-	src: usize,
-	dst: usize,
+/// `move {count} from {src + 1} to {dst + 1}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+	pub count: usize,
+	pub src: usize,
+	pub dst: usize,
+}
+
+impl Display for Move {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"move {} from {} to {}",
+			self.count,
+			self.src + 1,
+			self.dst + 1
+		)
+	}
 }
 
-impl Move {
-	fn from_str(s: &str) -> Self {
+impl FromStr for Move {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let bad = || ParseError::BadMove(s.to_string());
 		let words: Vec<&str> = s.split_whitespace().collect();
-		let count = words.get(1).unwrap().parse::<usize>().unwrap();
-		let src = words.get(3).unwrap().parse::<usize>().unwrap() - 1;
-		let dst = words.get(5).unwrap().parse::<usize>().unwrap() - 1;
-		Move { count, src, dst }
-	}
-}
-
-pub fn p1(lines: &Vec<String>) -> String {
-	let n = (lines[0].len() / 4) + 1; // to get number of sections
-	let mut harbour = Harbour::new(n);
-	for line in lines {
-		if line.is_empty() {
-			// moves start here
-			harbour.rev_stacks();
-		} else if line.starts_with("move") {
-			let m = Move::from_str(line);
-			assert!(m.src < n);
-			assert!(m.dst < n);
-			harbour.do_move(&m); // move normal
-		} else if line.contains('[') {
-			//"[_] [_] [_] [_] [_] [_] [_] [_] [_]"
-			for section in 0..n {
-				let container: char = line.chars().nth(1 + section * 4).unwrap();
-				if container.is_ascii_uppercase() {
-					harbour.push_container(section, container);
-				}
-			}
-		} else { //" 1   2   3   4   5   6   7   8   9 "
-		}
-	}
-	println!("After moves: {harbour}");
-	harbour.top_as_str()
-}
-
-pub fn p2(lines: &Vec<String>) -> String {
-	let n = (lines[0].len() / 4) + 1;
-	let mut harbour = Harbour::new(n);
-	for line in lines {
-		if line.is_empty() {
-			harbour.rev_stacks();
-		} else if line.starts_with("move") {
-			//"move 1 from 8 to 1"
-			let m = Move::from_str(line);
-			assert!(m.src < n);
-			assert!(m.dst < n);
-			harbour.do_move_crane(&m);
-		} else if line.contains('[') {
-			//"[1] [2] [3] [4] [5] [6] [7] [8] [9]"
-			for section in 0..n {
-				let container: char = line.chars().nth(1 + section * 4).unwrap();
-				if container.is_ascii_uppercase() {
-					harbour.push_container(section, container);
-				}
-			}
-		} else { //" 1   2   3   4   5   6   7   8   9 "
+		let [mv, count, from, src, to, dst] = words.as_slice() else {
+			return Err(bad());
+		};
+		if (*mv, *from, *to) != ("move", "from", "to") {
+			return Err(bad());
+		}
+		let count = count.parse::<usize>().map_err(|_| bad())?;
+		let src = src.parse::<usize>().map_err(|_| bad())?;
+		let dst = dst.parse::<usize>().map_err(|_| bad())?;
+		if src == 0 || dst == 0 {
+			return Err(bad());
+		}
+		Ok(Move {
+			count,
+			src: src - 1,
+			dst: dst - 1,
+		})
+	}
+}
+
+/// A crane model that knows how to carry out a `Move`.
+///
+/// Cranes only ever touch the top `m.count` crates of `m.src` and `m.dst`.
+pub trait Crane {
+	fn name(&self) -> String;
+
+	fn lift(&self, harbour: &mut Harbour, m: &Move) -> Result<(), MoveError>;
+}
+
+/// with old boring 9000 crane, one container at a time...
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+	fn name(&self) -> String {
+		"9000".to_string()
+	}
+
+	fn lift(&self, harbour: &mut Harbour, m: &Move) -> Result<(), MoveError> {
+		harbour.check(m)?;
+		for _ in 0..m.count {
+			let c = harbour.take(m.src, 1);
+			harbour.put(m.dst, c);
+		}
+		Ok(())
+	}
+}
+
+/// with crane 9001 can move multiple container in one move
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+	fn name(&self) -> String {
+		"9001".to_string()
+	}
+
+	fn lift(&self, harbour: &mut Harbour, m: &Move) -> Result<(), MoveError> {
+		harbour.check(m)?;
+		let v = harbour.take(m.src, m.count);
+		harbour.put(m.dst, v);
+		Ok(())
+	}
+}
+
+/// Lifts at most `k` containers at a time, keeping their order within a lift
+pub struct Capacity(usize);
+
+impl Capacity {
+	pub fn new(k: usize) -> Self {
+		assert!(k > 0, "crane must lift at least one container");
+		Capacity(k)
+	}
+}
+
+impl Crane for Capacity {
+	fn name(&self) -> String {
+		format!("capacity:{}", self.0)
+	}
+
+	fn lift(&self, harbour: &mut Harbour, m: &Move) -> Result<(), MoveError> {
+		harbour.check(m)?;
+		let mut left = m.count;
+		while left > 0 {
+			let n = left.min(self.0);
+			let v = harbour.take(m.src, n);
+			harbour.put(m.dst, v);
+			left -= n;
+		}
+		Ok(())
+	}
+}
+
+/// Two armed crane: when the destination has at least `count` containers the top `count` of
+/// both stacks are swapped, otherwise it moves them like the 9001.
+pub struct Swap;
+
+impl Crane for Swap {
+	fn name(&self) -> String {
+		"swap".to_string()
+	}
+
+	fn lift(&self, harbour: &mut Harbour, m: &Move) -> Result<(), MoveError> {
+		harbour.check(m)?;
+		if m.src == m.dst {
+			return Ok(());
+		}
+		let v = harbour.take(m.src, m.count);
+		if harbour.sections[m.dst].len() >= m.count {
+			let w = harbour.take(m.dst, m.count);
+			harbour.put(m.src, w);
 		}
+		harbour.put(m.dst, v);
+		Ok(())
+	}
+}
+
+/// Crane from its name: `9000`, `9001`, `capacity:<k>` or `swap`
+pub fn crane_by_name(name: &str) -> Option<Box<dyn Crane>> {
+	match name.split_once(':') {
+		Some(("capacity", k)) => match k.parse::<usize>() {
+			Ok(k) if k > 0 => Some(Box::new(Capacity::new(k))),
+			_ => None,
+		},
+		Some(_) => None,
+		None => match name {
+			"9000" => Some(Box::new(CrateMover9000)),
+			"9001" => Some(Box::new(CrateMover9001)),
+			"swap" => Some(Box::new(Swap)),
+			_ => None,
+		},
+	}
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+	Parse(ParseError),
+	Move(usize, MoveError),
+}
+
+/// Parse the puzzle and do every move with `crane`, returns the top containers
+pub fn run(lines: &[String], crane: &dyn Crane) -> Result<String, Error> {
+	let (mut harbour, moves) = Harbour::parse(lines).map_err(Error::Parse)?;
+	for (i, m) in moves.iter().enumerate() {
+		crane.lift(&mut harbour, m).map_err(|e| Error::Move(i, e))?;
 	}
-	println!("After moves: {harbour}");
-	harbour.top_as_str()
+	Ok(harbour.top_as_str())
+}
+
+pub fn p1(lines: &[String]) -> String {
+	run(lines, &CrateMover9000).unwrap()
+}
+
+pub fn p2(lines: &[String]) -> String {
+	run(lines, &CrateMover9001).unwrap()
 }
 
 #[cfg(test)]
@@ -169,22 +404,252 @@ mod tests {
 	#[test]
 	fn example2() {
 		let fd = std::fs::File::open("example.txt").unwrap();
-		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+		let lines: Vec<String> = std::io::BufReader::new(fd)
 			.lines()
 			.map(|l| l.unwrap())
 			.collect();
-		let r = p2(&mut lines);
+		let r = p2(&lines);
 		assert_eq!(r, "MCD");
 	}
 
 	#[test]
 	fn part2() {
 		let fd = std::fs::File::open("input.txt").unwrap();
-		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+		let lines: Vec<String> = std::io::BufReader::new(fd)
 			.lines()
 			.map(|l| l.unwrap())
 			.collect();
-		let r = p2(&mut lines);
+		let r = p2(&lines);
 		assert_eq!(r, "LBBVJBRMH");
 	}
+
+	#[test]
+	fn parse() {
+		// trailing spaces stripped, 10 stacks, multi-char crates
+		let drawing = "\
+[AB]                                    [J]
+[C]     [X]                             [K]
+ 1   2   3   4   5   6   7   8   9  10  11
+
+move 1 from 11 to 2
+move 2 from 1 to 10";
+		let lines: Vec<String> = drawing.lines().map(|l| l.to_string()).collect();
+		let (mut harbour, moves) = Harbour::parse(&lines).unwrap();
+		assert_eq!(harbour.sections.len(), 11);
+		assert_eq!(harbour.sections[0], ["C", "AB"]);
+		assert_eq!(harbour.sections[10], ["K", "J"]);
+		for m in &moves {
+			CrateMover9000.lift(&mut harbour, m).unwrap();
+		}
+		assert_eq!(harbour.top_as_str(), "JXCK");
+		assert_eq!(
+			CrateMover9000.lift(&mut harbour, &"move 3 from 2 to 1".parse().unwrap()),
+			Err(MoveError::NotEnoughCrates {
+				stack: 2,
+				have: 1,
+				want: 3
+			})
+		);
+		assert_eq!(
+			CrateMover9001.lift(&mut harbour, &"move 1 from 12 to 1".parse().unwrap()),
+			Err(MoveError::NoSuchStack(12))
+		);
+		assert!("move 1 from 0 to 1".parse::<Move>().is_err());
+		assert!("move one from 1 to 2".parse::<Move>().is_err());
+
+		let lines: Vec<String> = ["[A]", "    1"].iter().map(|l| l.to_string()).collect();
+		assert_eq!(Harbour::parse(&lines), Err(ParseError::StrayCrate(0, 0)));
+	}
+
+	#[test]
+	fn cranes() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let top = |name| run(&lines, crane_by_name(name).unwrap().as_ref()).unwrap();
+		assert_eq!(top("9000"), "CMZ");
+		assert_eq!(top("9001"), "MCD");
+		assert_eq!(top("capacity:1"), "CMZ");
+		assert_eq!(top("capacity:3"), "MCD");
+		assert_eq!(top("capacity:2"), "MCZ");
+		assert!(crane_by_name("capacity:0").is_none());
+		assert!(crane_by_name("9002").is_none());
+
+		let lines: Vec<String> = ["[A] [C]", "[B] [D]", " 1   2 "]
+			.iter()
+			.map(|l| l.to_string())
+			.collect();
+		let (mut harbour, _) = Harbour::parse(&lines).unwrap();
+		Swap.lift(&mut harbour, &"move 1 from 1 to 2".parse().unwrap())
+			.unwrap();
+		assert_eq!(harbour.top_as_str(), "CA");
+		Swap.lift(&mut harbour, &"move 2 from 2 to 1".parse().unwrap())
+			.unwrap();
+		assert_eq!(harbour.sections, [vec!["D", "A"], vec!["B", "C"]]);
+	}
+
+	#[test]
+	fn rope() {
+		assert_ne!(rope::generate(9, 50, 20, 2), rope::generate(9, 50, 20, 3));
+		for lines in [
+			rope::generate(9, 500, 2000, 3),
+			rope::generate(30, 40, 5000, 4),
+		] {
+			let (harbour, moves) = Harbour::parse(&lines).unwrap();
+			let (mut h9000, mut h9001) = (harbour.clone(), harbour.clone());
+			let mut r9000 = rope::RopeHarbour::from_harbour(&harbour);
+			let mut r9001 = r9000.clone();
+			for m in &moves {
+				CrateMover9000.lift(&mut h9000, m).unwrap();
+				CrateMover9001.lift(&mut h9001, m).unwrap();
+				r9000.move_9000(m).unwrap();
+				r9001.move_9001(m).unwrap();
+			}
+			assert_eq!(r9000.top_as_str(), h9000.top_as_str());
+			assert_eq!(r9001.top_as_str(), h9001.top_as_str());
+			for (i, stack) in h9000.sections.iter().enumerate() {
+				assert_eq!(&r9000.stack(i), stack);
+			}
+			for (i, stack) in h9001.sections.iter().enumerate() {
+				assert_eq!(&r9001.stack(i), stack);
+			}
+		}
+	}
+
+	#[test]
+	fn history() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let (harbour, moves) = Harbour::parse(&lines).unwrap();
+		let mut history = history::History::new(harbour.clone());
+		for m in &moves {
+			history.apply(&Capacity::new(2), m).unwrap();
+		}
+		assert_eq!(history.harbour().top_as_str(), "MCZ");
+		assert_eq!(history.last_touched("Z"), Some(2));
+		assert_eq!(history.last_touched("C"), Some(4));
+		assert_eq!(history.last_touched("P"), None);
+		assert_eq!(history.first_empty(0), Some(2));
+		assert_eq!(history.first_empty(1), Some(3));
+		assert_eq!(history.first_empty(2), None);
+
+		assert!(history.goto(0));
+		assert_eq!(history.harbour(), &harbour);
+		assert!(!history.undo());
+		assert_eq!(history.last_touched("C"), None);
+		assert!(history.redo());
+		assert_eq!(history.harbour().top_as_str(), "DCP");
+		assert!(history.goto(4));
+		assert_eq!(history.harbour().top_as_str(), "MCZ");
+		assert!(!history.goto(5));
+
+		// a new move after undo drops the rest
+		history.goto(1);
+		history
+			.apply(&CrateMover9001, &"move 1 from 3 to 2".parse().unwrap())
+			.unwrap();
+		assert_eq!((history.position(), history.len()), (2, 2));
+		assert_eq!(history.harbour().top_as_str(), "DP");
+		assert_eq!(history.moves().count(), 2);
+	}
+
+	#[test]
+	fn planner() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let (harbour, _) = Harbour::parse(&lines).unwrap();
+		assert_eq!(
+			planner::plan(&harbour, "NDP", &CrateMover9000, 1000),
+			Ok(vec![])
+		);
+		for crane in [&CrateMover9000 as &dyn Crane, &CrateMover9001] {
+			let moves = planner::plan(&harbour, "CMZ", crane, 100_000).unwrap();
+			assert!(moves.len() <= 4);
+			let script = planner::to_script(&moves);
+			assert_eq!(script.lines().count(), moves.len());
+			assert!(script.lines().all(|l| l.starts_with("move ")));
+			let mut replay = harbour.clone();
+			for line in script.lines() {
+				crane.lift(&mut replay, &line.parse().unwrap()).unwrap();
+			}
+			assert_eq!(replay.top_as_str(), "CMZ");
+		}
+		// needs both N and D on top of stack 1 at once
+		let moves = planner::plan(&harbour, "ZN", &CrateMover9000, 100_000).unwrap();
+		assert_eq!(moves.len(), 2);
+		assert_eq!(
+			planner::plan(&harbour, "QQQ", &CrateMover9000, 100_000),
+			Err(planner::PlanError::Unreachable)
+		);
+		assert_eq!(
+			planner::plan(&harbour, "CMZ", &CrateMover9000, 2),
+			Err(planner::PlanError::GaveUp)
+		);
+	}
+
+	#[test]
+	fn render() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let (mut harbour, moves) = Harbour::parse(&lines).unwrap();
+		assert_eq!(harbour.to_string(), lines[..4].join("\n") + "\n");
+		for m in &moves {
+			CrateMover9000.lift(&mut harbour, m).unwrap();
+			let dump: Vec<String> = harbour.to_string().lines().map(|l| l.to_string()).collect();
+			assert_eq!(Harbour::parse(&dump).unwrap().0, harbour);
+		}
+		assert_eq!(
+			harbour.to_string(),
+			"        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3 \n"
+		);
+
+		let lines: Vec<String> = ["[AB]    [long]", "[C] [D] [x]", " 1   2   3"]
+			.iter()
+			.map(|l| l.to_string())
+			.collect();
+		let (harbour, _) = Harbour::parse(&lines).unwrap();
+		let dump: Vec<String> = harbour.to_string().lines().map(|l| l.to_string()).collect();
+		assert_eq!(dump[0], "[AB]     [long]");
+		assert_eq!(Harbour::parse(&dump).unwrap().0, harbour);
+	}
+
+	#[ignore]
+	#[test]
+	fn rope_bench() {
+		let lines = rope::generate(9, 2_000_000, 1_000_000, 5);
+		let (harbour, moves) = Harbour::parse(&lines).unwrap();
+		let now = std::time::Instant::now();
+		let mut rope = rope::RopeHarbour::from_harbour(&harbour);
+		for m in &moves {
+			rope.move_9000(m).unwrap();
+		}
+		println!("rope 9000, {} moves: {:?}", moves.len(), now.elapsed());
+
+		// moving one crate at a time is too slow for all of them
+		let moves = &moves[..1000];
+		let mut rope = rope::RopeHarbour::from_harbour(&harbour);
+		let now = std::time::Instant::now();
+		for m in moves {
+			rope.move_9000(m).unwrap();
+		}
+		println!("rope 9000, {} moves: {:?}", moves.len(), now.elapsed());
+		let mut vec = harbour.clone();
+		let now = std::time::Instant::now();
+		for m in moves {
+			CrateMover9000.lift(&mut vec, m).unwrap();
+		}
+		println!("vec 9000, {} moves: {:?}", moves.len(), now.elapsed());
+		assert_eq!(rope.top_as_str(), vec.top_as_str());
+	}
 }
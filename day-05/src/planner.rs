@@ -0,0 +1,66 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{Crane, Harbour, Move};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PlanError {
+	/// every reachable arrangement was seen, none has the target on top
+	Unreachable,
+	/// `max_states` arrangements were seen before finding the target
+	GaveUp,
+}
+
+/// Shortest list of moves after which `harbour.top_as_str()` is `target`, using `crane`.
+///
+/// Breadth first search over every arrangement of the crates, giving up once `max_states`
+/// arrangements have been seen.
+pub fn plan(
+	harbour: &Harbour,
+	target: &str,
+	crane: &dyn Crane,
+	max_states: usize,
+) -> Result<Vec<Move>, PlanError> {
+	// arrangement => the move that reached it, and from where
+	let mut seen: HashMap<Harbour, Option<(Harbour, Move)>> = HashMap::new();
+	let mut todo = VecDeque::new();
+	seen.insert(harbour.clone(), None);
+	todo.push_back(harbour.clone());
+	while let Some(current) = todo.pop_front() {
+		if current.top_as_str() == target {
+			return Ok(path(&seen, current));
+		}
+		let n = current.sections.len();
+		for src in 0..n {
+			for dst in (0..n).filter(|&dst| dst != src) {
+				for count in 1..=current.sections[src].len() {
+					let m = Move { count, src, dst };
+					let mut next = current.clone();
+					if crane.lift(&mut next, &m).is_err() || seen.contains_key(&next) {
+						continue;
+					}
+					if seen.len() >= max_states {
+						return Err(PlanError::GaveUp);
+					}
+					seen.insert(next.clone(), Some((current.clone(), m)));
+					todo.push_back(next);
+				}
+			}
+		}
+	}
+	Err(PlanError::Unreachable)
+}
+
+fn path(seen: &HashMap<Harbour, Option<(Harbour, Move)>>, mut at: Harbour) -> Vec<Move> {
+	let mut moves = vec![];
+	while let Some(Some((prev, m))) = seen.get(&at) {
+		moves.push(*m);
+		at = prev.clone();
+	}
+	moves.reverse();
+	moves
+}
+
+/// Moves in the puzzle input syntax, one per line
+pub fn to_script(moves: &[Move]) -> String {
+	moves.iter().map(|m| format!("{m}\n")).collect()
+}
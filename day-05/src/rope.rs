@@ -0,0 +1,303 @@
+//! Harbour where a move costs O(log n) however many containers it carries.
+//!
+//! Every stack is an implicit treap (a randomized balanced tree keyed by position) with a lazy
+//! reversal flag. Moving `count` containers splits them off the top of one tree and merges them
+//! onto the other; the 9000 crane, which reverses them, only flips a flag on the moved tree.
+
+use crate::{Harbour, Move, MoveError};
+
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+struct Node {
+	/// index into `RopeHarbour::names`
+	name: usize,
+	prio: u64,
+	left: usize,
+	right: usize,
+	size: usize,
+	/// children still have to be swapped, all the way down
+	rev: bool,
+}
+
+/// xorshift generator for reproducible random input
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+	fn new(seed: u64) -> Self {
+		// one splitmix64 step, so nearby seeds give unrelated states
+		let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+		z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+		z ^= z >> 31;
+		// state must never be 0
+		Rng(if z == 0 { 1 } else { z })
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+
+	/// Some number below `n`
+	fn below(&mut self, n: usize) -> usize {
+		(self.next_u64() % n as u64) as usize
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct RopeHarbour {
+	names: Vec<String>,
+	nodes: Vec<Node>,
+	/// tree of every stack, bottom first
+	roots: Vec<usize>,
+	rng: Rng,
+}
+
+impl RopeHarbour {
+	/// Stacks of crates, bottom first
+	pub fn new(stacks: Vec<Vec<String>>) -> Self {
+		let mut rope = RopeHarbour {
+			names: vec![],
+			nodes: vec![],
+			roots: vec![NIL; stacks.len()],
+			rng: Rng::new(0),
+		};
+		for (i, stack) in stacks.into_iter().enumerate() {
+			for name in stack {
+				let node = rope.node(name);
+				rope.roots[i] = rope.merge(rope.roots[i], node);
+			}
+		}
+		rope
+	}
+
+	pub fn from_harbour(harbour: &Harbour) -> Self {
+		Self::new(harbour.sections.clone())
+	}
+
+	fn node(&mut self, name: String) -> usize {
+		let prio = self.rng.next_u64();
+		self.names.push(name);
+		self.nodes.push(Node {
+			name: self.names.len() - 1,
+			prio,
+			left: NIL,
+			right: NIL,
+			size: 1,
+			rev: false,
+		});
+		self.nodes.len() - 1
+	}
+
+	fn size(&self, t: usize) -> usize {
+		if t == NIL {
+			0
+		} else {
+			self.nodes[t].size
+		}
+	}
+
+	fn update(&mut self, t: usize) {
+		self.nodes[t].size = 1 + self.size(self.nodes[t].left) + self.size(self.nodes[t].right);
+	}
+
+	fn flip(&mut self, t: usize) {
+		if t != NIL {
+			self.nodes[t].rev ^= true;
+		}
+	}
+
+	/// Apply a pending reversal to the children of `t`
+	fn push(&mut self, t: usize) {
+		if self.nodes[t].rev {
+			let node = &mut self.nodes[t];
+			node.rev = false;
+			std::mem::swap(&mut node.left, &mut node.right);
+			let (left, right) = (node.left, node.right);
+			self.flip(left);
+			self.flip(right);
+		}
+	}
+
+	/// First `k` elements of `t`, and the rest
+	fn split(&mut self, t: usize, k: usize) -> (usize, usize) {
+		if t == NIL {
+			return (NIL, NIL);
+		}
+		self.push(t);
+		let left = self.nodes[t].left;
+		if self.size(left) >= k {
+			let (a, b) = self.split(left, k);
+			self.nodes[t].left = b;
+			self.update(t);
+			(a, t)
+		} else {
+			let k = k - self.size(left) - 1;
+			let (a, b) = self.split(self.nodes[t].right, k);
+			self.nodes[t].right = a;
+			self.update(t);
+			(t, b)
+		}
+	}
+
+	/// `a` followed by `b`
+	fn merge(&mut self, a: usize, b: usize) -> usize {
+		if a == NIL {
+			return b;
+		}
+		if b == NIL {
+			return a;
+		}
+		if self.nodes[a].prio > self.nodes[b].prio {
+			self.push(a);
+			self.nodes[a].right = self.merge(self.nodes[a].right, b);
+			self.update(a);
+			a
+		} else {
+			self.push(b);
+			self.nodes[b].left = self.merge(a, self.nodes[b].left);
+			self.update(b);
+			b
+		}
+	}
+
+	fn check(&self, m: &Move) -> Result<(), MoveError> {
+		for stack in [m.src, m.dst] {
+			if stack >= self.roots.len() {
+				return Err(MoveError::NoSuchStack(stack + 1));
+			}
+		}
+		let have = self.size(self.roots[m.src]);
+		if have < m.count {
+			return Err(MoveError::NotEnoughCrates {
+				stack: m.src + 1,
+				have,
+				want: m.count,
+			});
+		}
+		Ok(())
+	}
+
+	fn do_move(&mut self, m: &Move, reverse: bool) -> Result<(), MoveError> {
+		self.check(m)?;
+		if m.src == m.dst {
+			// one at a time back onto the same stack changes nothing either
+			return Ok(());
+		}
+		let src = self.roots[m.src];
+		let (keep, top) = self.split(src, self.size(src) - m.count);
+		if reverse {
+			self.flip(top);
+		}
+		self.roots[m.src] = keep;
+		self.roots[m.dst] = self.merge(self.roots[m.dst], top);
+		Ok(())
+	}
+
+	/// Same as `CrateMover9000`
+	pub fn move_9000(&mut self, m: &Move) -> Result<(), MoveError> {
+		self.do_move(m, true)
+	}
+
+	/// Same as `CrateMover9001`
+	pub fn move_9001(&mut self, m: &Move) -> Result<(), MoveError> {
+		self.do_move(m, false)
+	}
+
+	pub fn len(&self, stack: usize) -> usize {
+		self.size(self.roots[stack])
+	}
+
+	/// Top of the stack, following pending reversals without applying them
+	pub fn top(&self, stack: usize) -> Option<&str> {
+		let mut t = self.roots[stack];
+		let mut rev = false;
+		while t != NIL {
+			let node = &self.nodes[t];
+			rev ^= node.rev;
+			let next = if rev { node.left } else { node.right };
+			if next == NIL {
+				return Some(&self.names[node.name]);
+			}
+			t = next;
+		}
+		None
+	}
+
+	pub fn top_as_str(&self) -> String {
+		(0..self.roots.len()).filter_map(|i| self.top(i)).collect()
+	}
+
+	/// Containers of a stack, bottom first
+	pub fn stack(&self, stack: usize) -> Vec<&str> {
+		let mut v = Vec::with_capacity(self.len(stack));
+		// (node, reversal inherited from the ancestors, children already pushed)
+		let mut todo = vec![(self.roots[stack], false, false)];
+		while let Some((t, rev, expanded)) = todo.pop() {
+			if t == NIL {
+				continue;
+			}
+			let node = &self.nodes[t];
+			if expanded {
+				v.push(self.names[node.name].as_str());
+				continue;
+			}
+			let rev = rev ^ node.rev;
+			let (first, last) = if rev {
+				(node.right, node.left)
+			} else {
+				(node.left, node.right)
+			};
+			todo.push((last, rev, false));
+			todo.push((t, rev, true));
+			todo.push((first, rev, false));
+		}
+		v
+	}
+}
+
+/// Puzzle input with `stacks` stacks, `crates` containers and `moves` valid moves
+pub fn generate(stacks: usize, crates: usize, moves: usize, seed: u64) -> Vec<String> {
+	let mut rng = Rng::new(seed);
+	let mut rand = move |n: usize| rng.below(n);
+	let mut sizes = vec![0; stacks];
+	for _ in 0..crates {
+		sizes[rand(stacks)] += 1;
+	}
+	let height = sizes.iter().copied().max().unwrap_or(0);
+	let mut lines = vec![];
+	for y in (0..height).rev() {
+		let row: Vec<String> = sizes
+			.iter()
+			.map(|&size| {
+				if size > y {
+					format!("[{}]", (b'A' + rand(26) as u8) as char)
+				} else {
+					"   ".to_string()
+				}
+			})
+			.collect();
+		lines.push(row.join(" "));
+	}
+	let labels: Vec<String> = (1..=stacks).map(|i| format!("{i:^3}")).collect();
+	lines.push(labels.join(" "));
+	lines.push(String::new());
+	for _ in 0..moves {
+		let src = loop {
+			let src = rand(stacks);
+			if sizes[src] > 0 {
+				break src;
+			}
+		};
+		let dst = rand(stacks);
+		let count = 1 + rand(sizes[src]);
+		sizes[src] -= count;
+		sizes[dst] += count;
+		lines.push(format!("move {count} from {} to {}", src + 1, dst + 1));
+	}
+	lines
+}
@@ -1,40 +1,266 @@
-fn has_repeating(s: &[char]) -> bool {
-	for (i, c) in s.iter().enumerate() {
-		for h in &s[i + 1..] {
-			if c == h {
-				return true;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::io::Read;
+
+/// Window of the last `k` symbols, with how often each one occurs in it
+#[derive(Debug, Clone)]
+struct Window<T> {
+	k: usize,
+	symbols: VecDeque<T>,
+	counts: HashMap<T, usize>,
+	/// symbols occurring more than once in the window
+	repeated: usize,
+}
+
+impl<T: Eq + Hash + Copy> Window<T> {
+	fn new(k: usize) -> Self {
+		Window {
+			k,
+			symbols: VecDeque::with_capacity(k + 1),
+			counts: HashMap::new(),
+			repeated: 0,
+		}
+	}
+
+	/// Slide the window over one more symbol
+	fn push(&mut self, c: T) {
+		self.symbols.push_back(c);
+		let n = self.counts.entry(c).or_insert(0);
+		*n += 1;
+		if *n == 2 {
+			self.repeated += 1;
+		}
+		if self.symbols.len() > self.k {
+			let old = self.symbols.pop_front().unwrap();
+			let n = self.counts.get_mut(&old).unwrap();
+			*n -= 1;
+			if *n == 1 {
+				self.repeated -= 1;
+			} else if *n == 0 {
+				self.counts.remove(&old);
 			}
 		}
 	}
-	false
+
+	/// Full, and every symbol in it is different
+	fn is_marker(&self) -> bool {
+		self.symbols.len() == self.k && self.repeated == 0
+	}
 }
 
-pub fn p1(lines: &Vec<String>) -> Vec<usize> {
+/// Number of symbols read when the last `k` of them are first all different.
+/// Works on anything hashable (`line.bytes()`, `line.chars()`, ...) in O(n).
+pub fn find_marker<T: Eq + Hash + Copy>(
+	stream: impl IntoIterator<Item = T>,
+	k: usize,
+) -> Option<usize> {
+	if k == 0 {
+		return Some(0);
+	}
+	let mut window = Window::new(k);
+	for (pos, c) in stream.into_iter().enumerate() {
+		window.push(c);
+		if window.is_marker() {
+			return Some(pos + 1);
+		}
+	}
+	None
+}
+
+/// End offsets of every marker of `k` different symbols; markers don't overlap, the search
+/// for the next one starts right after the previous.
+pub fn all_markers<T: Eq + Hash + Copy>(
+	stream: impl IntoIterator<Item = T>,
+	k: usize,
+) -> Vec<usize> {
+	assert!(k > 0);
 	let mut v = vec![];
-	for line in lines {
-		'w: for (pos, window) in line.chars().collect::<Vec<_>>().windows(4).enumerate() {
-			println!("[{pos}]: {}", window.to_vec().iter().collect::<String>());
-			if !has_repeating(window) {
-				v.push(pos + window.len());
-				break 'w;
-			}
+	let mut window = Window::new(k);
+	for (pos, c) in stream.into_iter().enumerate() {
+		window.push(c);
+		if window.is_marker() {
+			v.push(pos + 1);
+			window = Window::new(k);
 		}
 	}
 	v
 }
 
-pub fn p2(lines: &Vec<String>) -> Vec<usize> {
-	let mut v = vec![];
-	for line in lines {
-		'w: for (pos, window) in line.chars().collect::<Vec<_>>().windows(14).enumerate() {
-			println!("[{pos}]: {}", window.to_vec().iter().collect::<String>());
-			if !has_repeating(window) {
-				v.push(pos + window.len());
-				break 'w;
+/// One packet of a framed stream: its marker, and everything up to the next marker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet<'a> {
+	/// offset of the first marker byte
+	pub marker: usize,
+	/// offset of the first payload byte, right after the marker
+	pub start: usize,
+	pub payload: &'a [u8],
+}
+
+/// Split a stream into packets, each starting with a start-of-packet marker of size `k`.
+/// Anything before the first marker is dropped.
+pub fn frame(stream: &[u8], k: usize) -> Vec<Packet<'_>> {
+	let ends = all_markers(stream.iter().copied(), k);
+	let mut packets = vec![];
+	for (i, &start) in ends.iter().enumerate() {
+		let end = ends.get(i + 1).map_or(stream.len(), |next| next - k);
+		packets.push(Packet {
+			marker: start - k,
+			start,
+			payload: &stream[start..end],
+		});
+	}
+	packets
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+	/// stream offset right after the start-of-packet marker
+	StartOfPacket(usize),
+	/// stream offset right after the start-of-message marker
+	StartOfMessage(usize),
+}
+
+/// Finds the start-of-packet and start-of-message markers of a byte stream fed in chunks of
+/// any size; a marker split across chunks is found all the same.
+#[derive(Debug, Clone)]
+pub struct MarkerScanner {
+	offset: usize,
+	packet: Option<Window<u8>>,
+	message: Option<Window<u8>>,
+}
+
+impl Default for MarkerScanner {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl MarkerScanner {
+	/// Puzzle marker sizes, 4 for packets and 14 for messages
+	pub fn new() -> Self {
+		Self::with_sizes(4, 14)
+	}
+
+	pub fn with_sizes(packet: usize, message: usize) -> Self {
+		assert!(packet > 0 && message > 0);
+		MarkerScanner {
+			offset: 0,
+			packet: Some(Window::new(packet)),
+			message: Some(Window::new(message)),
+		}
+	}
+
+	/// Bytes seen so far
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+
+	/// Both markers found, the rest of the stream doesn't matter
+	pub fn is_done(&self) -> bool {
+		self.packet.is_none() && self.message.is_none()
+	}
+
+	/// Scan the next chunk, returns the markers that end in it
+	pub fn feed(&mut self, chunk: &[u8]) -> Vec<Event> {
+		let mut events = vec![];
+		for (i, &b) in chunk.iter().enumerate() {
+			if self.is_done() {
+				self.offset += chunk.len() - i;
+				break;
+			}
+			self.offset += 1;
+			if Self::found(&mut self.packet, b) {
+				events.push(Event::StartOfPacket(self.offset));
+			}
+			if Self::found(&mut self.message, b) {
+				events.push(Event::StartOfMessage(self.offset));
 			}
 		}
+		events
+	}
+
+	/// Push `b`, dropping the window once it becomes a marker
+	fn found(window: &mut Option<Window<u8>>, b: u8) -> bool {
+		let Some(w) = window else {
+			return false;
+		};
+		w.push(b);
+		if w.is_marker() {
+			*window = None;
+			return true;
+		}
+		false
+	}
+
+	/// Scan a whole reader, until both markers are found or it runs dry
+	pub fn scan(&mut self, mut reader: impl Read) -> std::io::Result<Vec<Event>> {
+		let mut events = vec![];
+		let mut buf = [0u8; 4096];
+		while !self.is_done() {
+			let n = match reader.read(&mut buf) {
+				Ok(0) => break,
+				Ok(n) => n,
+				Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+				Err(e) => return Err(e),
+			};
+			events.extend(self.feed(&buf[..n]));
+		}
+		Ok(events)
 	}
-	v
+}
+
+/// Distinct window statistics of a stream, see `window_stats`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowStats {
+	/// for every position, length of the longest all-different window ending there
+	pub runs: Vec<usize>,
+	/// (start, len) of the first longest all-different window
+	pub longest: (usize, usize),
+	/// how many windows of size `k` are all different
+	pub distinct: usize,
+}
+
+/// All in one pass: remembering where every symbol was last seen gives the start of the
+/// longest all-different window ending at each position.
+pub fn window_stats<T: Eq + Hash + Copy>(
+	stream: impl IntoIterator<Item = T>,
+	k: usize,
+) -> WindowStats {
+	let mut last: HashMap<T, usize> = HashMap::new();
+	let mut stats = WindowStats {
+		runs: vec![],
+		longest: (0, 0),
+		distinct: 0,
+	};
+	let mut start = 0;
+	for (pos, c) in stream.into_iter().enumerate() {
+		if let Some(prev) = last.insert(c, pos) {
+			start = start.max(prev + 1);
+		}
+		let run = pos + 1 - start;
+		stats.runs.push(run);
+		if run > stats.longest.1 {
+			stats.longest = (start, run);
+		}
+		if k > 0 && run >= k {
+			stats.distinct += 1;
+		}
+	}
+	stats
+}
+
+pub fn p1(lines: &[String]) -> Vec<usize> {
+	lines
+		.iter()
+		.filter_map(|l| find_marker(l.chars(), 4))
+		.collect()
+}
+
+pub fn p2(lines: &[String]) -> Vec<usize> {
+	lines
+		.iter()
+		.filter_map(|l| find_marker(l.chars(), 14))
+		.collect()
 }
 
 #[cfg(test)]
@@ -51,6 +277,9 @@ mod tests {
 			.collect();
 		let r = p1(&lines);
 		assert_eq!(r, [7, 5, 6, 10, 11]);
+		// both parts count chars, not bytes
+		let lines = ["ééabcdéfghijklmn".to_string()];
+		assert_eq!((p1(&lines), p2(&lines)), (vec![5], vec![16]));
 	}
 
 	#[test]
@@ -67,22 +296,132 @@ mod tests {
 	#[test]
 	fn example2() {
 		let fd = std::fs::File::open("example.txt").unwrap();
-		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+		let lines: Vec<String> = std::io::BufReader::new(fd)
 			.lines()
 			.map(|l| l.unwrap())
 			.collect();
-		let r = p2(&mut lines);
+		let r = p2(&lines);
 		assert_eq!(r, [19, 23, 23, 29, 26]);
 	}
 
 	#[test]
 	fn part2() {
 		let fd = std::fs::File::open("input.txt").unwrap();
-		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+		let lines: Vec<String> = std::io::BufReader::new(fd)
 			.lines()
 			.map(|l| l.unwrap())
 			.collect();
-		let r = p2(&mut lines);
+		let r = p2(&lines);
 		assert_eq!(r, [2773]);
 	}
+
+	#[test]
+	fn markers() {
+		assert_eq!(find_marker("aab".bytes(), 2), Some(3));
+		assert_eq!(find_marker("aaaa".chars(), 2), None);
+		assert_eq!(find_marker("abc".chars(), 4), None);
+		assert_eq!(find_marker("αβαγδ".chars(), 3), Some(4));
+		assert_eq!(find_marker([1, 2, 3], 0), Some(0));
+		assert_eq!(find_marker([7, 7, 7, 1, 2, 3, 4, 5], 5), Some(7));
+	}
+
+	#[test]
+	fn scanner() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let (packets, messages) = (p1(&lines), p2(&lines));
+		for (i, line) in lines.iter().enumerate() {
+			let expected = vec![
+				Event::StartOfPacket(packets[i]),
+				Event::StartOfMessage(messages[i]),
+			];
+			for size in 1..=line.len() {
+				let mut scanner = MarkerScanner::new();
+				let mut events = vec![];
+				for chunk in line.as_bytes().chunks(size) {
+					events.extend(scanner.feed(chunk));
+				}
+				assert_eq!(events, expected);
+				assert!(scanner.is_done());
+				assert_eq!(scanner.offset(), line.len());
+			}
+		}
+
+		// a reader handing out 3 bytes at a time
+		struct Trickle<'a>(&'a [u8]);
+		impl Read for Trickle<'_> {
+			fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+				let n = self.0.len().min(buf.len()).min(3);
+				buf[..n].copy_from_slice(&self.0[..n]);
+				self.0 = &self.0[n..];
+				Ok(n)
+			}
+		}
+		let fd = std::fs::File::open("input.txt").unwrap();
+		let events = MarkerScanner::new().scan(Trickle(&std::fs::read("input.txt").unwrap()));
+		assert_eq!(
+			events.unwrap(),
+			[Event::StartOfPacket(1965), Event::StartOfMessage(2773)]
+		);
+		let events = MarkerScanner::new().scan(fd).unwrap();
+		assert_eq!(events.len(), 2);
+		let events = MarkerScanner::new().scan("aaaa".as_bytes()).unwrap();
+		assert!(events.is_empty());
+	}
+
+	#[test]
+	fn framing() {
+		let stream = b"zzzabcaabbaabbbcadccdcd";
+		assert_eq!(all_markers(stream.iter(), 4), [6, 18]);
+		let packets = frame(stream, 4);
+		assert_eq!(
+			packets,
+			[
+				Packet {
+					marker: 2,
+					start: 6,
+					payload: b"aabbaabb"
+				},
+				Packet {
+					marker: 14,
+					start: 18,
+					payload: b"ccdcd"
+				}
+			]
+		);
+
+		// the next marker may start right after the previous one
+		assert_eq!(all_markers("abcdefgh".chars(), 4), [4, 8]);
+		assert!(frame(b"aaaa", 2).is_empty());
+	}
+
+	#[test]
+	fn stats() {
+		let stats = window_stats("abcabcbb".chars(), 3);
+		assert_eq!(stats.runs, [1, 2, 3, 3, 3, 3, 2, 1]);
+		assert_eq!(stats.longest, (0, 3));
+		assert_eq!(stats.distinct, 4);
+		assert_eq!(window_stats("pwwkew".bytes(), 2).longest, (2, 3));
+		assert_eq!(window_stats("".bytes(), 2).longest, (0, 0));
+
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		for line in &lines {
+			let stats = window_stats(line.bytes(), 14);
+			let first = stats.runs.iter().position(|&run| run >= 14).map(|i| i + 1);
+			assert_eq!(first, find_marker(line.bytes(), 14));
+			let brute = line
+				.as_bytes()
+				.windows(14)
+				.filter(|w| find_marker(w.iter(), 14).is_some())
+				.count();
+			assert_eq!(stats.distinct, brute);
+		}
+	}
 }
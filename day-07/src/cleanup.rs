@@ -0,0 +1,151 @@
+//! What to delete to get enough free space on a `Device`.
+
+use crate::query::{walk, Found};
+use crate::{Device, ROOT};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CleanupError {
+	/// the files take more than the whole disk
+	TooFull { used: usize, capacity: usize },
+	/// even an empty disk wouldn't have that much free space
+	Impossible { required: usize, capacity: usize },
+}
+
+/// Bytes that have to be deleted for `required` bytes to be free on a disk of `capacity`
+pub fn need(dev: &Device, capacity: usize, required: usize) -> Result<usize, CleanupError> {
+	let used = dev.get_total_size();
+	if used > capacity {
+		return Err(CleanupError::TooFull { used, capacity });
+	}
+	if required > capacity {
+		return Err(CleanupError::Impossible { required, capacity });
+	}
+	Ok(required.saturating_sub(capacity - used))
+}
+
+/// Smallest single dir (the root included) big enough to free the space, the puzzle's answer
+pub fn smallest_dir(dev: &Device, capacity: usize, required: usize) -> Result<Found, CleanupError> {
+	let need = need(dev, capacity, required)?;
+	let (id, dir) = dev
+		.all_dirs()
+		.filter(|(_, dir)| dir.size() >= need)
+		.min_by_key(|(_, dir)| dir.size())
+		// the root always qualifies
+		.unwrap();
+	let path = dev.path(id);
+	Ok(Found {
+		depth: if id == ROOT {
+			0
+		} else {
+			path.matches('/').count()
+		},
+		path,
+		size: dir.size(),
+		is_dir: true,
+	})
+}
+
+/// Dirs and files to delete
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+	pub deleted: Vec<Found>,
+	pub freed: usize,
+	/// the search finished, so nothing frees less while still freeing enough
+	pub optimal: bool,
+}
+
+/// Set of dirs and files freeing enough space while deleting as few bytes as possible.
+///
+/// Branch and bound over the tree in depth first order: every entry is either deleted, skipping
+/// everything below it, or kept and its content considered. A branch is cut when it can't beat
+/// the best plan so far or can't free enough anymore. The search starts from the
+/// `smallest_dir` answer and gives up after `max_steps` branches, returning the best plan found.
+pub fn plan(
+	dev: &Device,
+	capacity: usize,
+	required: usize,
+	max_steps: usize,
+) -> Result<Plan, CleanupError> {
+	let need = need(dev, capacity, required)?;
+	if need == 0 {
+		return Ok(Plan {
+			deleted: vec![],
+			freed: 0,
+			optimal: true,
+		});
+	}
+	let entries = walk(dev);
+	let n = entries.len();
+	// end[i]: first entry after everything below i
+	let mut end = vec![n; n];
+	let mut open: Vec<usize> = vec![];
+	for (i, e) in entries.iter().enumerate() {
+		while let Some(&j) = open.last() {
+			if entries[j].depth < e.depth {
+				break;
+			}
+			end[j] = i;
+			open.pop();
+		}
+		open.push(i);
+	}
+	// avail[i]: most that can still be freed when deciding from entry i on
+	let mut avail = vec![0; n + 1];
+	for i in (0..n).rev() {
+		avail[i] = entries[i].size + avail[end[i]];
+	}
+
+	const NONE: usize = usize::MAX;
+	// deleted entries, as (entry, previously deleted) chains
+	let mut taken: Vec<(usize, usize)> = vec![];
+	let single = smallest_dir(dev, capacity, required)?;
+	let mut best = (single.size, NONE);
+	let mut best_is_single = true;
+	// (next entry, freed so far, last deleted)
+	let mut todo = vec![(0, 0, NONE)];
+	let mut steps = 0;
+	let mut exhausted = false;
+	while let Some((i, freed, last)) = todo.pop() {
+		if steps == max_steps {
+			exhausted = true;
+			break;
+		}
+		steps += 1;
+		if freed >= need {
+			if freed < best.0 {
+				best = (freed, last);
+				best_is_single = false;
+			}
+			continue;
+		}
+		if i == n || freed + avail[i] < need || freed >= best.0 {
+			continue;
+		}
+		// keep i, look below it
+		todo.push((i + 1, freed, last));
+		// delete i, tried first
+		let size = entries[i].size;
+		if freed + size < best.0 {
+			taken.push((i, last));
+			todo.push((end[i], freed + size, taken.len() - 1));
+		}
+	}
+
+	let deleted = if best_is_single {
+		vec![single]
+	} else {
+		let mut v = vec![];
+		let mut at = best.1;
+		while at != NONE {
+			v.push(entries[taken[at].0].found(dev));
+			at = taken[at].1;
+		}
+		v.reverse();
+		v
+	};
+	Ok(Plan {
+		freed: best.0,
+		deleted,
+		optimal: !exhausted,
+	})
+}
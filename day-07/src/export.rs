@@ -0,0 +1,379 @@
+//! Snapshots of a `Device`, as JSON or as a canonical terminal session.
+
+use crate::{Device, DeviceError, DirId, Entry, ROOT};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum JsonError {
+	/// malformed JSON at this byte offset
+	Syntax(usize),
+	/// valid JSON that isn't a dir, found at this path
+	Shape(String),
+	/// stored size of the dir at this path doesn't match its content
+	SizeMismatch(String),
+	Device(DeviceError),
+}
+
+impl From<DeviceError> for JsonError {
+	fn from(e: DeviceError) -> Self {
+		JsonError::Device(e)
+	}
+}
+
+fn quote(s: &str) -> String {
+	let mut out = String::from("\"");
+	for c in s.chars() {
+		match c {
+			'"' => out += "\\\"",
+			'\\' => out += "\\\\",
+			'\n' => out += "\\n",
+			'\t' => out += "\\t",
+			c if c.is_control() => out += &format!("\\u{:04x}", c as u32),
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+	out
+}
+
+/// A dir up to the opening of its `dirs` list, closed right away when it has no sub dirs
+fn write_head(dev: &Device, id: DirId, out: &mut String) {
+	let dir = dev.dir(id);
+	*out += &format!(
+		"{{\"name\": {}, \"size\": {}, \"files\": [",
+		quote(dir.name()),
+		dir.size()
+	);
+	for (i, (name, size)) in dir.vars().iter().enumerate() {
+		let sep = if i == 0 { "" } else { ", " };
+		*out += &format!("{sep}{{\"name\": {}, \"size\": {size}}}", quote(name));
+	}
+	*out += "], \"dirs\": [";
+	if dir.dirs().is_empty() {
+		*out += "]}";
+	}
+}
+
+/// Nested dirs with their files and sizes, entries sorted by name.
+/// Every dir starts a line and nothing is indented, so the text stays linear in the tree size
+/// however deep it goes.
+pub fn to_json(dev: &Device) -> String {
+	let mut out = String::new();
+	write_head(dev, ROOT, &mut out);
+	// sub dirs still to write, and whether one was written already
+	let mut todo = vec![(dev.dir(ROOT).dirs().values(), false)];
+	while let Some((subs, started)) = todo.last_mut() {
+		if let Some(&sub) = subs.next() {
+			out += if *started { ",\n" } else { "\n" };
+			*started = true;
+			write_head(dev, sub, &mut out);
+			let subs = dev.dir(sub).dirs();
+			if !subs.is_empty() {
+				todo.push((subs.values(), false));
+			}
+		} else {
+			if *started {
+				out += "\n]}";
+			}
+			todo.pop();
+		}
+	}
+	out.push('\n');
+	out
+}
+
+enum Json {
+	Str(String),
+	Num(usize),
+	List(Vec<Json>),
+	/// fields in order of appearance
+	Obj(Fields),
+}
+
+type Fields = Vec<(String, Json)>;
+
+impl Json {
+	/// Move the items or field values out, leaving an empty list or object
+	fn take_children(&mut self) -> Vec<Json> {
+		match self {
+			Json::List(items) => std::mem::take(items),
+			Json::Obj(fields) => std::mem::take(fields).into_iter().map(|(_, v)| v).collect(),
+			_ => vec![],
+		}
+	}
+}
+
+impl Drop for Json {
+	/// Without this, dropping a deeply nested value recurses once per level
+	fn drop(&mut self) {
+		let mut todo = self.take_children();
+		while let Some(mut json) = todo.pop() {
+			todo.extend(json.take_children());
+		}
+	}
+}
+
+/// Value being parsed, waiting for its items
+enum Open {
+	List(Vec<Json>),
+	/// fields so far and the key of the next value
+	Obj(Fields, String),
+}
+
+struct Parser<'a> {
+	text: &'a [u8],
+	at: usize,
+}
+
+impl Parser<'_> {
+	fn skip_space(&mut self) {
+		while self.text.get(self.at).is_some_and(u8::is_ascii_whitespace) {
+			self.at += 1;
+		}
+	}
+
+	/// Consume `c`, after any whitespace
+	fn eat(&mut self, c: u8) -> bool {
+		self.skip_space();
+		let found = self.text.get(self.at) == Some(&c);
+		if found {
+			self.at += 1;
+		}
+		found
+	}
+
+	fn expect(&mut self, c: u8) -> Result<(), JsonError> {
+		if self.eat(c) {
+			Ok(())
+		} else {
+			Err(JsonError::Syntax(self.at))
+		}
+	}
+
+	/// Key of an object field, with its `:`
+	fn key(&mut self) -> Result<String, JsonError> {
+		self.skip_space();
+		let key = self.string()?;
+		self.expect(b':')?;
+		Ok(key)
+	}
+
+	/// Parse a value, keeping open lists and objects on a stack instead of recursing
+	fn value(&mut self) -> Result<Json, JsonError> {
+		let mut open: Vec<Open> = vec![];
+		loop {
+			self.skip_space();
+			let mut value = match self.text.get(self.at) {
+				Some(b'"') => Json::Str(self.string()?),
+				Some(b'0'..=b'9') => {
+					let start = self.at;
+					while self.text.get(self.at).is_some_and(u8::is_ascii_digit) {
+						self.at += 1;
+					}
+					std::str::from_utf8(&self.text[start..self.at])
+						.unwrap()
+						.parse()
+						.map(Json::Num)
+						.map_err(|_| JsonError::Syntax(start))?
+				}
+				Some(b'[') => {
+					self.at += 1;
+					if !self.eat(b']') {
+						open.push(Open::List(vec![]));
+						continue;
+					}
+					Json::List(vec![])
+				}
+				Some(b'{') => {
+					self.at += 1;
+					if !self.eat(b'}') {
+						open.push(Open::Obj(vec![], self.key()?));
+						continue;
+					}
+					Json::Obj(vec![])
+				}
+				_ => return Err(JsonError::Syntax(self.at)),
+			};
+			// hand the value to the innermost open one, closing those that are complete
+			loop {
+				match open.last_mut() {
+					None => return Ok(value),
+					Some(Open::List(items)) => {
+						items.push(value);
+						if !self.eat(b']') {
+							self.expect(b',')?;
+							break;
+						}
+					}
+					Some(Open::Obj(fields, key)) => {
+						fields.push((std::mem::take(key), value));
+						if !self.eat(b'}') {
+							self.expect(b',')?;
+							*key = self.key()?;
+							break;
+						}
+					}
+				}
+				value = match open.pop().unwrap() {
+					Open::List(items) => Json::List(items),
+					Open::Obj(fields, _) => Json::Obj(fields),
+				};
+			}
+		}
+	}
+
+	fn string(&mut self) -> Result<String, JsonError> {
+		let bad = |at| JsonError::Syntax(at);
+		if self.text.get(self.at) != Some(&b'"') {
+			return Err(bad(self.at));
+		}
+		self.at += 1;
+		let mut bytes = vec![];
+		loop {
+			let c = *self.text.get(self.at).ok_or(bad(self.at))?;
+			self.at += 1;
+			match c {
+				b'"' => break,
+				b'\\' => {
+					let c = *self.text.get(self.at).ok_or(bad(self.at))?;
+					self.at += 1;
+					let c = match c {
+						b'"' | b'\\' | b'/' => c as char,
+						b'n' => '\n',
+						b't' => '\t',
+						b'r' => '\r',
+						b'b' => '\u{8}',
+						b'f' => '\u{c}',
+						b'u' => {
+							let hex = self.text.get(self.at..self.at + 4).ok_or(bad(self.at))?;
+							let code = std::str::from_utf8(hex)
+								.ok()
+								.and_then(|h| u32::from_str_radix(h, 16).ok())
+								.and_then(char::from_u32)
+								.ok_or(bad(self.at))?;
+							self.at += 4;
+							code
+						}
+						_ => return Err(bad(self.at - 1)),
+					};
+					bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+				}
+				c => bytes.push(c),
+			}
+		}
+		String::from_utf8(bytes).map_err(|_| bad(self.at))
+	}
+}
+
+/// Field `key` of a JSON object
+fn field<'a>(fields: &'a Fields, key: &str) -> Option<&'a Json> {
+	fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+/// Name and size of a `{"name": .., "size": ..}` object, plus the object itself
+fn named(json: &Json) -> Option<(&str, usize, &Fields)> {
+	let Json::Obj(fields) = json else {
+		return None;
+	};
+	match (field(fields, "name"), field(fields, "size")) {
+		(Some(Json::Str(name)), Some(Json::Num(size))) => Some((name, *size, fields)),
+		_ => None,
+	}
+}
+
+/// Add the content of a JSON dir below the root of `dev`, returning the stored size of
+/// every dir under it
+fn read_dirs(dev: &mut Device, root: &Fields) -> Result<Vec<(DirId, usize)>, JsonError> {
+	let mut sizes = vec![];
+	let mut todo = vec![(ROOT, root)];
+	while let Some((id, fields)) = todo.pop() {
+		dev.ptr = id;
+		let shape = |dev: &Device| JsonError::Shape(dev.pwd());
+		let (Some(Json::List(files)), Some(Json::List(dirs))) =
+			(field(fields, "files"), field(fields, "dirs"))
+		else {
+			return Err(shape(dev));
+		};
+		let mut entries = vec![];
+		for file in files {
+			let (name, size, _) = named(file).ok_or_else(|| shape(dev))?;
+			entries.push(Entry::Var(name.to_string(), size));
+		}
+		for dir in dirs {
+			let (name, _, _) = named(dir).ok_or_else(|| shape(dev))?;
+			entries.push(Entry::Dir(name.to_string()));
+		}
+		dev.ls(&entries)?;
+		for dir in dirs {
+			let (name, size, fields) = named(dir).unwrap();
+			let sub = dev.dirs[id].dirs[name];
+			sizes.push((sub, size));
+			todo.push((sub, fields));
+		}
+	}
+	dev.ptr = ROOT;
+	Ok(sizes)
+}
+
+/// Rebuild a `Device` from `to_json` output, checking the stored sizes
+pub fn from_json(text: &str) -> Result<Device, JsonError> {
+	let mut parser = Parser {
+		text: text.as_bytes(),
+		at: 0,
+	};
+	let json = parser.value()?;
+	parser.skip_space();
+	if parser.at != text.len() {
+		return Err(JsonError::Syntax(parser.at));
+	}
+	let mut dev = Device::new();
+	let (name, size, fields) = named(&json).ok_or_else(|| JsonError::Shape("/".to_string()))?;
+	if name != "/" {
+		return Err(JsonError::Shape("/".to_string()));
+	}
+	let mut sizes = read_dirs(&mut dev, fields)?;
+	sizes.push((ROOT, size));
+	for (id, size) in sizes {
+		if dev.dir(id).size() != size {
+			return Err(JsonError::SizeMismatch(dev.path(id)));
+		}
+	}
+	Ok(dev)
+}
+
+/// Session listing every dir once, in name order, that `Device::from_lines` turns back into
+/// the same tree
+pub fn transcript(dev: &Device) -> Vec<String> {
+	let mut lines = vec!["$ cd /".to_string()];
+	// (dir, entered) where entered means its listing was already printed
+	let mut todo = vec![(ROOT, false)];
+	while let Some((id, entered)) = todo.pop() {
+		let dir = dev.dir(id);
+		if entered {
+			if id != ROOT {
+				lines.push("$ cd ..".to_string());
+			}
+			continue;
+		}
+		if id != ROOT {
+			lines.push(format!("$ cd {}", dir.name()));
+		}
+		lines.push("$ ls".to_string());
+		let mut entries: Vec<(&str, String)> = dir
+			.dirs()
+			.keys()
+			.map(|name| (name.as_str(), format!("dir {name}")))
+			.chain(
+				dir.vars()
+					.iter()
+					.map(|(name, size)| (name.as_str(), format!("{size} {name}"))),
+			)
+			.collect();
+		entries.sort();
+		lines.extend(entries.into_iter().map(|(_, line)| line));
+		todo.push((id, true));
+		for &child in dir.dirs().values().rev() {
+			todo.push((child, false));
+		}
+	}
+	lines
+}
@@ -1,182 +1,407 @@
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::cell::Cell;
+use std::collections::BTreeMap;
 
-#[derive(Debug)]
-enum DeviceError {
-	DirAlreadyExists(DirError),
+pub mod cleanup;
+pub mod export;
+pub mod query;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeviceError {
+	DirAlreadyExists,
 	VarAlreadyExists,
-	DirNotFound,
+	/// path that couldn't be resolved
+	DirNotFound(String),
 	DirAboveRoot,
+	/// dir at this path was listed twice with different contents
+	ListingConflict(String),
+	/// line of the session that isn't a command or a listing entry
+	BadLine(String),
+	/// name that can't be a path component: empty, `.`, `..`, containing `/` or control chars,
+	/// or with whitespace around it
+	BadName(String),
+}
+
+/// One line of `$ ls` output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+	Dir(String),
+	Var(String, usize),
+}
+
+impl Entry {
+	pub fn name(&self) -> &str {
+		match self {
+			Entry::Dir(name) | Entry::Var(name, _) => name,
+		}
+	}
+
+	pub fn parse(line: &str) -> Result<Self, DeviceError> {
+		let bad = || DeviceError::BadLine(line.to_string());
+		let (first, name) = line.trim().split_once(' ').ok_or_else(bad)?;
+		if first == "dir" {
+			Ok(Entry::Dir(name.to_string()))
+		} else {
+			let size = first.parse().map_err(|_| bad())?;
+			Ok(Entry::Var(name.to_string(), size))
+		}
+	}
 }
 
-struct Device {
-	root: Rc<RefCell<Dir>>,
-	ptr: Rc<RefCell<Dir>>,
+/// A name must survive a trip through a session line: `$ cd` trims it and lines end at `\n`
+fn check_name(name: &str) -> Result<(), DeviceError> {
+	if name.is_empty()
+		|| name == "."
+		|| name == ".."
+		|| name.contains('/')
+		|| name.trim() != name
+		|| name.contains(char::is_control)
+	{
+		return Err(DeviceError::BadName(name.to_string()));
+	}
+	Ok(())
+}
+
+/// Index of a dir in the `Device` arena
+pub type DirId = usize;
+
+/// The root dir is always the first one
+pub const ROOT: DirId = 0;
+
+/// Filesystem as an arena of dirs linked by index.
+///
+/// A dir is always added after its parent, so parents have smaller ids than their children
+/// and walking the arena backwards visits every dir before its parent.
+pub struct Device {
+	dirs: Vec<Dir>,
+	ptr: DirId,
+	/// a file was added since the sizes were last summed up
+	stale: Cell<bool>,
+}
+
+impl Default for Device {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Device {
-	fn new() -> Self {
-		let root = Rc::new(RefCell::new(Dir::new()));
-		let ptr = Rc::clone(&root);
-		Self { root, ptr }
-	}
-
-	fn add_dir(&mut self, name: &str) -> Result<(), DeviceError> {
-		let dir = self
-			.ptr
-			.borrow_mut()
-			.add_dir(name)
-			.map_err(DeviceError::DirAlreadyExists)?;
-		dir.borrow_mut().root = Some(self.ptr.clone());
+	pub fn new() -> Self {
+		Self {
+			dirs: vec![Dir::new("/", None)],
+			ptr: ROOT,
+			stale: Cell::new(false),
+		}
+	}
+
+	/// Replay a terminal session
+	pub fn from_lines(lines: &[String]) -> Result<Self, DeviceError> {
+		let mut dev = Device::new();
+		let mut lines = lines.iter().peekable();
+		while let Some(line) = lines.next() {
+			if let Some(path) = line.strip_prefix("$ cd ") {
+				dev.cd(path.trim())?;
+			} else if line.trim() == "$ ls" {
+				let mut entries = vec![];
+				while let Some(line) = lines.next_if(|l| !l.starts_with('$')) {
+					entries.push(Entry::parse(line)?);
+				}
+				dev.ls(&entries)?;
+			} else if !line.trim().is_empty() {
+				return Err(DeviceError::BadLine(line.to_string()));
+			}
+		}
+		Ok(dev)
+	}
+
+	/// Record `$ ls` output for the current dir.
+	///
+	/// Listing a dir again is fine as long as it shows the same entries.
+	pub fn ls(&mut self, entries: &[Entry]) -> Result<(), DeviceError> {
+		let conflict = |dev: &Self| DeviceError::ListingConflict(dev.pwd());
+		// everything is checked before anything is added, so a bad listing leaves the dir as it was
+		for entry in entries {
+			check_name(entry.name())?;
+		}
+		let mut names = std::collections::HashSet::new();
+		let mut new = vec![];
+		for entry in entries {
+			let dir = &self.dirs[self.ptr];
+			let name = entry.name();
+			let exists = match entry {
+				Entry::Dir(_) => dir.dirs.contains_key(name),
+				Entry::Var(_, size) => dir.vars.get(name).is_some_and(|known| known == size),
+			};
+			if !names.insert(name) {
+				return Err(conflict(self));
+			}
+			if exists {
+				continue;
+			}
+			if dir.listed || dir.dirs.contains_key(name) || dir.vars.contains_key(name) {
+				// a new entry in a dir listed before, or one that changed kind or size
+				return Err(conflict(self));
+			}
+			new.push(entry);
+		}
+		let dir = &self.dirs[self.ptr];
+		if dir.dirs.len() + dir.vars.len() + new.len() != names.len() {
+			// something listed before is missing now
+			return Err(conflict(self));
+		}
+		for entry in new {
+			match entry {
+				Entry::Dir(name) => self.add_dir(name).map(|_| ())?,
+				Entry::Var(name, size) => self.add_var(name, *size)?,
+			}
+		}
+		self.dirs[self.ptr].listed = true;
 		Ok(())
 	}
 
-	fn add_var(&mut self, name: &str, size: usize) -> Result<(), DeviceError> {
-		self.ptr
-			.borrow_mut()
-			.add_var(name, size)
-			.map_err(|_| DeviceError::VarAlreadyExists)?;
+	/// Fails when the current dir already has a dir or a file with that name
+	fn check_free(&self, name: &str) -> Result<(), DeviceError> {
+		check_name(name)?;
+		let dir = &self.dirs[self.ptr];
+		if dir.dirs.contains_key(name) {
+			return Err(DeviceError::DirAlreadyExists);
+		}
+		if dir.vars.contains_key(name) {
+			return Err(DeviceError::VarAlreadyExists);
+		}
 		Ok(())
 	}
 
-	fn cd(&mut self, name: Option<&str>) -> Result<(), DeviceError> {
-		let dir: Rc<RefCell<Dir>> = match name {
-			Some(name) => {
-				match self
-					.ptr
-					.borrow()
-					.dirs
-					.iter()
-					.find(|v| v.borrow().name == name)
-				{
-					Some(r) => r.clone(),
-					None => return Err(DeviceError::DirNotFound),
-				}
-			}
-			None => match &self.ptr.borrow().root {
-				Some(r) => r.clone(),
-				None => return Err(DeviceError::DirAboveRoot),
-			},
+	pub fn add_dir(&mut self, name: &str) -> Result<DirId, DeviceError> {
+		self.check_free(name)?;
+		let id = self.dirs.len();
+		self.dirs.push(Dir::new(name, Some(self.ptr)));
+		self.dirs[self.ptr].dirs.insert(name.to_string(), id);
+		Ok(id)
+	}
+
+	pub fn add_var(&mut self, name: &str, size: usize) -> Result<(), DeviceError> {
+		self.check_free(name)?;
+		self.dirs[self.ptr].vars.insert(name.to_string(), size);
+		self.stale.set(true);
+		Ok(())
+	}
+
+	/// Dir at `path`, absolute (`/a/b`) or relative to the current dir (`a/../b`)
+	pub fn lookup(&self, path: &str) -> Result<DirId, DeviceError> {
+		let mut id = if path.starts_with('/') {
+			ROOT
+		} else {
+			self.ptr
 		};
-		self.ptr = dir;
+		for name in path.split('/') {
+			id = match name {
+				"" | "." => continue,
+				".." => self.dirs[id].parent.ok_or(DeviceError::DirAboveRoot)?,
+				name => *self.dirs[id]
+					.dirs
+					.get(name)
+					.ok_or_else(|| DeviceError::DirNotFound(path.to_string()))?,
+			};
+		}
+		Ok(id)
+	}
+
+	pub fn cd(&mut self, path: &str) -> Result<(), DeviceError> {
+		self.ptr = self.lookup(path)?;
 		Ok(())
 	}
 
-	fn get_all_dirs(&self) -> Vec<Rc<RefCell<Dir>>> {
-		return self.root.borrow().get_sub_dirs();
+	/// Absolute path of the current dir
+	pub fn pwd(&self) -> String {
+		self.path(self.ptr)
 	}
 
-	fn get_total_size(&self) -> usize {
-		self.root.borrow_mut().calc_size();
-		self.root.borrow().size
+	pub fn dir(&self, id: DirId) -> &Dir {
+		self.calc_sizes();
+		&self.dirs[id]
 	}
-}
 
-#[derive(Debug)]
-enum DirError {
-	DirAlreadyExists,
-	VarAlreadyExists,
+	/// Every dir with its id, the root first
+	pub fn all_dirs(&self) -> impl Iterator<Item = (DirId, &Dir)> {
+		self.calc_sizes();
+		self.dirs.iter().enumerate()
+	}
+
+	/// Absolute path of a dir, following the parent links
+	pub fn path(&self, id: DirId) -> String {
+		let mut names = vec![];
+		let mut id = id;
+		while let Some(parent) = self.dirs[id].parent {
+			names.push(self.dirs[id].name.as_str());
+			id = parent;
+		}
+		names.reverse();
+		format!("/{}", names.join("/"))
+	}
+
+	/// Every dir below the root
+	fn get_all_dirs(&self) -> impl Iterator<Item = &Dir> {
+		self.calc_sizes();
+		self.dirs.iter().skip(1)
+	}
+
+	/// Dirs that showed up in a listing but were never listed themselves
+	pub fn unlisted(&self) -> Vec<DirId> {
+		(0..self.dirs.len())
+			.filter(|&id| !self.dirs[id].listed)
+			.collect()
+	}
+
+	/// Whether the dir and every dir below it were listed, so its size is exact
+	/// and not just a lower bound
+	pub fn is_size_known(&self, id: DirId) -> bool {
+		let mut todo = vec![id];
+		while let Some(id) = todo.pop() {
+			if !self.dirs[id].listed {
+				return false;
+			}
+			todo.extend(self.dirs[id].dirs.values());
+		}
+		true
+	}
+
+	/// Sizes of every dir, children first thanks to the arena order.
+	/// Only does the work when files were added since the last time, so every read of a
+	/// size calls it and a run of `ls`/`add_var` costs a single pass.
+	fn calc_sizes(&self) {
+		if !self.stale.replace(false) {
+			return;
+		}
+		for dir in &self.dirs {
+			dir.size.set(dir.vars.values().sum());
+		}
+		for id in (1..self.dirs.len()).rev() {
+			let (size, parent) = (self.dirs[id].size.get(), self.dirs[id].parent.unwrap());
+			let parent = &self.dirs[parent].size;
+			parent.set(parent.get() + size);
+		}
+	}
+
+	pub fn get_total_size(&self) -> usize {
+		self.dir(ROOT).size()
+	}
 }
 
-struct Dir {
+pub struct Dir {
 	name: String,
-	size: usize,
-	root: Option<Rc<RefCell<Dir>>>,
-	dirs: Vec<Rc<RefCell<Dir>>>,
-	vars: Vec<(String, usize)>,
+	/// kept up to date by `Device::calc_sizes`
+	size: Cell<usize>,
+	parent: Option<DirId>,
+	dirs: BTreeMap<String, DirId>,
+	vars: BTreeMap<String, usize>,
+	/// got `$ ls` output at least once
+	listed: bool,
 }
 
 impl Dir {
-	fn new() -> Self {
+	fn new(name: &str, parent: Option<DirId>) -> Self {
 		Dir {
-			name: "/".to_string(),
-			size: 0,
-			root: None,
-			dirs: vec![],
-			vars: vec![],
+			name: name.to_string(),
+			size: Cell::new(0),
+			parent,
+			dirs: BTreeMap::new(),
+			vars: BTreeMap::new(),
+			listed: false,
 		}
 	}
 
-	fn add_dir(&mut self, name: &str) -> Result<Rc<RefCell<Self>>, DirError> {
-		if self.dirs.iter().any(|v| v.borrow().name == name) {
-			return Err(DirError::DirAlreadyExists);
-		}
-		let dir = Dir {
-			name: name.to_string(),
-			size: 0,
-			root: None, //TODO!
-			dirs: vec![],
-			vars: vec![],
-		};
-		let dir = Rc::new(RefCell::new(dir));
-		self.dirs.push(dir.clone());
-		Ok(dir)
-	}
-
-	fn add_var(&mut self, name: &str, size: usize) -> Result<(), DirError> {
-		if self
-			.vars
-			.iter()
-			.any(|(var_name, _var_size)| var_name == name)
-		{
-			return Err(DirError::VarAlreadyExists);
-		}
-		self.vars.push((name.to_string(), size));
-		Ok(())
+	pub fn name(&self) -> &str {
+		&self.name
 	}
 
-	fn get_sub_dirs(&self) -> Vec<Rc<RefCell<Dir>>> {
-		let mut v: Vec<Rc<RefCell<Dir>>> = vec![];
-		for dir in self.dirs.iter() {
-			for sub_dir in dir.borrow().get_sub_dirs() {
-				v.push(sub_dir.clone());
-			}
-			v.push(dir.clone());
-		}
-		v
+	/// Total size, including every sub dir
+	pub fn size(&self) -> usize {
+		self.size.get()
+	}
+
+	pub fn parent(&self) -> Option<DirId> {
+		self.parent
+	}
+
+	/// Sub dirs by name
+	pub fn dirs(&self) -> &BTreeMap<String, DirId> {
+		&self.dirs
+	}
+
+	/// Files by name, with their size
+	pub fn vars(&self) -> &BTreeMap<String, usize> {
+		&self.vars
+	}
+}
+
+/// xorshift generator for reproducible random input
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+	fn new(seed: u64) -> Self {
+		// one splitmix64 step, so nearby seeds give unrelated states
+		let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+		z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+		z ^= z >> 31;
+		// state must never be 0
+		Rng(if z == 0 { 1 } else { z })
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
 	}
 
-	fn calc_size(&mut self) {
-		self.size = 0;
-		for (_name, size) in self.vars.iter() {
-			self.size += size;
+	/// Some number below `n`
+	fn below(&mut self, n: usize) -> usize {
+		(self.next_u64() % n as u64) as usize
+	}
+}
+
+/// Terminal session exploring a random tree of `dirs` dirs, each holding a file
+pub fn generate(dirs: usize, seed: u64) -> Vec<String> {
+	let mut rng = Rng::new(seed);
+	let mut rand = move |n: usize| rng.below(n);
+	let mut children: Vec<Vec<usize>> = vec![vec![]; dirs.max(1)];
+	for id in 1..dirs {
+		children[rand(id)].push(id);
+	}
+	let mut lines = vec!["$ cd /".to_string()];
+	// (dir, entered) where entered means its listing was already printed
+	let mut todo = vec![(0, false)];
+	while let Some((id, entered)) = todo.pop() {
+		if entered {
+			if id != 0 {
+				lines.push("$ cd ..".to_string());
+			}
+			continue;
+		}
+		if id != 0 {
+			lines.push(format!("$ cd d{id}"));
 		}
-		for dir in self.dirs.iter() {
-			dir.borrow_mut().calc_size();
-			self.size += dir.borrow().size;
+		lines.push("$ ls".to_string());
+		for &child in &children[id] {
+			lines.push(format!("dir d{child}"));
+		}
+		lines.push(format!("{} f{id}.txt", 1 + rand(100_000)));
+		todo.push((id, true));
+		for &child in children[id].iter().rev() {
+			todo.push((child, false));
 		}
 	}
+	lines
 }
 
-pub fn p1(lines: &Vec<String>) -> usize {
+pub fn p1(lines: &[String]) -> usize {
 	const MAX_SIZE: usize = 100_000;
-	let mut dev = Device::new();
-
-	for line in lines {
-		if line.starts_with("$ cd /") {
-			dev.cd(Some("/")).unwrap();
-		} else if line.starts_with("$ ls") {
-			// TODO: dev.ls(); ?
-		} else if line.starts_with("dir ") {
-			let name = line.split_whitespace().last().unwrap();
-			dev.add_dir(name).unwrap();
-		} else if line.starts_with("$ cd ..") {
-			dev.cd(None).unwrap();
-		} else if line.starts_with("$ cd ") {
-			let name: String = line.split_whitespace().last().unwrap().to_string();
-			dev.cd(Some(&name)).unwrap();
-		} else if line.chars().next().unwrap().is_numeric() {
-			let mut words = line.split_whitespace();
-			let size: usize = words.next().unwrap().parse().unwrap();
-			let name: String = words.next().unwrap().to_string();
-			dev.add_var(&name, size).unwrap();
-		}
-	}
-	dev.get_total_size();
+	let dev = Device::from_lines(lines).unwrap();
 	dev.get_all_dirs()
-		.iter()
 		.filter_map(|v| {
-			let size = v.borrow().size;
+			let size = v.size();
 			if size <= MAX_SIZE {
 				Some(size)
 			} else {
@@ -186,53 +411,14 @@ pub fn p1(lines: &Vec<String>) -> usize {
 		.sum()
 }
 
-pub fn p2(lines: &Vec<String>) -> usize {
+pub fn p2(lines: &[String]) -> usize {
 	const MAX_SIZE: usize = 70_000_000;
 	const REQ_SIZE: usize = 30_000_000;
-	const TOP_SIZE: usize = MAX_SIZE - REQ_SIZE;
-
-	let mut dev = Device::new();
-
-	for line in lines {
-		if line.starts_with("$ cd /") {
-			dev.cd(Some("/")).unwrap();
-		} else if line.starts_with("$ ls") {
-			//TODO: dev.ls()?;
-		} else if line.starts_with("dir ") {
-			let name = line.split_whitespace().last().unwrap();
-			dev.add_dir(name).unwrap();
-		} else if line.starts_with("$ cd ..") {
-			dev.cd(None).unwrap();
-		} else if line.starts_with("$ cd ") {
-			let name: String = line.split_whitespace().last().unwrap().to_string();
-			dev.cd(Some(&name)).unwrap();
-		} else if line.chars().next().unwrap().is_numeric() {
-			let mut words = line.split_whitespace();
-			let size: usize = words.next().unwrap().parse().unwrap();
-			let name: String = words.next().unwrap().to_string();
-			dev.add_var(&name, size).unwrap();
-		}
-	}
-	let total = dev.get_total_size();
-	assert!(total < MAX_SIZE);
-	assert!(total > TOP_SIZE);
-
-	let target = total - TOP_SIZE;
 
-	// let mut canditates = std::collections::BTreeSet::new();
-	// for dir in dev.get_all_dirs() {
-	//	 canditates.insert(dir.borrow().size);
-	// }
-	//return (&canditates).range(target..).next().unwrap();
-
-	let mut smallest = total;
-	for dir in dev.get_all_dirs() {
-		let size = dir.borrow().size;
-		if (size >= target) && (size < smallest) {
-			smallest = size;
-		}
-	}
-	smallest
+	let dev = Device::from_lines(lines).unwrap();
+	cleanup::smallest_dir(&dev, MAX_SIZE, REQ_SIZE)
+		.unwrap()
+		.size
 }
 
 #[cfg(test)]
@@ -265,22 +451,399 @@ mod tests {
 	#[test]
 	fn example2() {
 		let fd = std::fs::File::open("example.txt").unwrap();
-		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+		let lines: Vec<String> = std::io::BufReader::new(fd)
 			.lines()
 			.map(|l| l.unwrap())
 			.collect();
-		let r = p2(&mut lines);
+		let r = p2(&lines);
 		assert_eq!(r, 24933642);
 	}
 
 	#[test]
 	fn part2() {
 		let fd = std::fs::File::open("input.txt").unwrap();
-		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+		let lines: Vec<String> = std::io::BufReader::new(fd)
 			.lines()
 			.map(|l| l.unwrap())
 			.collect();
-		let r = p2(&mut lines);
+		let r = p2(&lines);
 		assert_eq!(r, 8679207);
 	}
+
+	#[test]
+	fn paths() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let mut dev = Device::from_lines(&lines).unwrap();
+		assert_eq!(dev.pwd(), "/d");
+		dev.cd("/a/e").unwrap();
+		assert_eq!(dev.pwd(), "/a/e");
+		dev.cd("../../d/./../a").unwrap();
+		assert_eq!(dev.pwd(), "/a");
+		dev.cd("e/..//e").unwrap();
+		assert_eq!(dev.pwd(), "/a/e");
+		assert_eq!(
+			dev.cd("/a/x"),
+			Err(DeviceError::DirNotFound("/a/x".to_string()))
+		);
+		assert_eq!(dev.cd("../../.."), Err(DeviceError::DirAboveRoot));
+		// failed cd stays put
+		assert_eq!(dev.pwd(), "/a/e");
+		dev.cd("/").unwrap();
+		assert_eq!(dev.pwd(), "/");
+
+		assert_eq!(dev.get_total_size(), 48381165);
+		assert_eq!(dev.dir(dev.lookup("a").unwrap()).size(), 94853);
+		assert_eq!(dev.path(dev.lookup("/a/e").unwrap()), "/a/e");
+	}
+
+	#[test]
+	fn arena() {
+		let lines = generate(20_000, 9);
+		assert_ne!(lines, generate(20_000, 8));
+		let dev = Device::from_lines(&lines).unwrap();
+		assert_eq!(dev.dirs.len(), 20_000);
+		let files: usize = dev.dirs.iter().flat_map(|d| d.vars().values()).sum();
+		assert_eq!(dev.get_total_size(), files);
+
+		// deep enough to overflow the stack with recursion
+		let mut lines = vec!["$ cd /".to_string()];
+		for _ in 0..200_000 {
+			lines.extend(["$ ls", "dir x", "1 f", "$ cd x"].map(String::from));
+		}
+		let dev = Device::from_lines(&lines).unwrap();
+		assert_eq!(dev.get_total_size(), 200_000);
+		assert_eq!(dev.pwd().len(), 2 * 200_000);
+
+		// sizes follow files added after the fact
+		let mut dev = Device::new();
+		dev.add_var("a", 10).unwrap();
+		assert_eq!(dev.get_total_size(), 10);
+		let sub = dev.add_dir("d").unwrap();
+		dev.cd("d").unwrap();
+		dev.add_var("b", 5).unwrap();
+		assert_eq!((dev.dir(sub).size(), dev.get_total_size()), (5, 15));
+	}
+
+	#[test]
+	fn queries() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let dev = Device::from_lines(&lines).unwrap();
+		let tree = "\
+- / (dir, size=48381165)
+  - a (dir, size=94853)
+    - e (dir, size=584)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir, size=24933642)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+";
+		assert_eq!(query::tree(&dev), tree);
+
+		let du: Vec<_> = query::du(&dev).into_iter().map(|f| f.path).collect();
+		assert_eq!(du, ["/", "/d", "/a", "/a/e"]);
+		assert_eq!(query::human(584), "584");
+		assert_eq!(query::human(94853), "93K");
+		assert_eq!(query::human(8504156), "8.1M");
+
+		let args = ["-name", "*.*", "-size", "+6000000"].map(String::from);
+		let found = query::find(&dev, &query::Filter::parse(&args).unwrap());
+		let found: Vec<_> = found.into_iter().map(|f| f.path).collect();
+		assert_eq!(found, ["/b.txt", "/c.dat", "/d/d.log"]);
+		let args = ["-type", "d", "-size", "-100001"].map(String::from);
+		let found = query::find(&dev, &query::Filter::parse(&args).unwrap());
+		assert_eq!(found.iter().map(|f| f.size).sum::<usize>(), 95437);
+		assert!(query::Filter::parse(&["-size".to_string()]).is_err());
+		assert!(query::glob_match("d.?o*", "d.log"));
+		assert!(!query::glob_match("*.ext", "d.log"));
+		assert!(query::glob_match("*", ""));
+
+		let top = query::top_files(&dev, 2);
+		assert_eq!((top[0].size, top[1].path.as_str()), (14848514, "/c.dat"));
+
+		// paths are only built for what is returned, so deep trees stay linear
+		let mut lines = vec!["$ cd /".to_string()];
+		for i in 0..30_000 {
+			lines.extend(["$ ls".to_string(), "dir x".to_string(), format!("{i} f")]);
+			lines.push("$ cd x".to_string());
+		}
+		let dev = Device::from_lines(&lines).unwrap();
+		assert_eq!(query::walk(&dev).len(), 2 * 30_000 + 1);
+		let top = query::top_files(&dev, 1);
+		assert_eq!((top[0].size, top[0].depth), (29_999, 30_000));
+		let args = ["-size", "+29998", "-type", "f"].map(String::from);
+		assert_eq!(
+			query::find(&dev, &query::Filter::parse(&args).unwrap()).len(),
+			1
+		);
+	}
+
+	#[test]
+	fn cleanup() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let dev = Device::from_lines(&lines).unwrap();
+		let plan = cleanup::plan(&dev, 70_000_000, 30_000_000, 1_000_000).unwrap();
+		assert!(plan.optimal);
+		assert_eq!(plan.freed, 8504156);
+		assert_eq!(plan.deleted[0].path, "/c.dat");
+		// out of steps before finding it
+		for steps in [0, 1] {
+			let plan = cleanup::plan(&dev, 70_000_000, 30_000_000, steps).unwrap();
+			assert!(!plan.optimal);
+			assert_eq!(plan.freed, 24933642);
+			assert_eq!(plan.deleted[0].path, "/d");
+		}
+		// already enough room
+		let plan = cleanup::plan(&dev, 70_000_000, 20_000_000, 1_000_000).unwrap();
+		assert_eq!((plan.freed, plan.deleted.len()), (0, 0));
+		// only everything will do
+		let plan = cleanup::plan(&dev, 70_000_000, 70_000_000, 1_000_000).unwrap();
+		assert_eq!(plan.freed, dev.get_total_size());
+		assert_eq!(
+			cleanup::plan(&dev, 40_000_000, 1, 10).err(),
+			Some(cleanup::CleanupError::TooFull {
+				used: 48381165,
+				capacity: 40_000_000
+			})
+		);
+		assert!(cleanup::smallest_dir(&dev, 70_000_000, 80_000_000).is_err());
+
+		// only the winning dir gets a path
+		let mut lines = vec!["$ cd /".to_string()];
+		for _ in 0..30_000 {
+			lines.extend(["$ ls", "dir x", "1 f", "$ cd x"].map(String::from));
+		}
+		let dev = Device::from_lines(&lines).unwrap();
+		let found = cleanup::smallest_dir(&dev, 30_000, 10).unwrap();
+		assert_eq!((found.size, found.depth), (10, 29_990));
+		assert_eq!(found.path.len(), 2 * 29_990);
+
+		let fd = std::fs::File::open("input.txt").unwrap();
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let dev = Device::from_lines(&lines).unwrap();
+		let plan = cleanup::plan(&dev, 70_000_000, 30_000_000, 10_000_000).unwrap();
+		let need = cleanup::need(&dev, 70_000_000, 30_000_000).unwrap();
+		assert!(plan.freed >= need && plan.freed <= p2(&lines));
+		assert_eq!(
+			plan.deleted.iter().map(|f| f.size).sum::<usize>(),
+			plan.freed
+		);
+		// nothing deleted twice
+		for a in &plan.deleted {
+			for b in &plan.deleted {
+				assert!(a == b || !b.path.starts_with(&format!("{}/", a.path)));
+			}
+		}
+	}
+
+	#[test]
+	fn export() {
+		for file in ["example.txt", "input.txt"] {
+			let fd = std::fs::File::open(file).unwrap();
+			let lines: Vec<String> = std::io::BufReader::new(fd)
+				.lines()
+				.map(|l| l.unwrap())
+				.collect();
+			let dev = Device::from_lines(&lines).unwrap();
+			let json = export::to_json(&dev);
+			let back = export::from_json(&json).unwrap();
+			assert_eq!(export::to_json(&back), json);
+			assert_eq!(query::tree(&back), query::tree(&dev));
+
+			let session = export::transcript(&dev);
+			let replayed = Device::from_lines(&session).unwrap();
+			assert_eq!(export::to_json(&replayed), json);
+			assert_eq!(export::transcript(&replayed), session);
+		}
+
+		// the user-046 tree, too deep for recursion
+		let mut lines = vec!["$ cd /".to_string()];
+		for _ in 0..200_000 {
+			lines.extend(["$ ls", "1 f", "dir x", "$ cd x"].map(String::from));
+		}
+		lines.push("$ ls".to_string());
+		let dev = Device::from_lines(&lines).unwrap();
+		let json = export::to_json(&dev);
+		let back = export::from_json(&json).unwrap();
+		assert_eq!(back.get_total_size(), 200_000);
+		assert_eq!(export::to_json(&back), json);
+		assert_eq!(export::transcript(&back)[..lines.len()], lines);
+
+		let session = export::transcript(&Device::from_lines(&generate(1000, 7)).unwrap());
+		assert_eq!(session.len(), generate(1000, 7).len());
+
+		// awkward names that are still valid come back unchanged both ways
+		let mut dev = Device::new();
+		let entries = [
+			Entry::Var("a \"b\"\\ é".to_string(), 3),
+			Entry::Dir("dir x".to_string()),
+			Entry::Dir("1 y".to_string()),
+		];
+		dev.ls(&entries).unwrap();
+		dev.cd("dir x").unwrap();
+		dev.ls(&[Entry::Var("$ ls".to_string(), 2)]).unwrap();
+		let json = export::to_json(&dev);
+		assert_eq!(export::to_json(&export::from_json(&json).unwrap()), json);
+		let replayed = Device::from_lines(&export::transcript(&dev)).unwrap();
+		assert_eq!(export::to_json(&replayed), json);
+
+		let ok = r#"{"name": "/", "size": 5, "files": [{"name": "a", "size": 5}], "dirs": [
+			{"name": "d", "size": 0, "files": [], "dirs": []}
+		]}"#;
+		assert_eq!(export::from_json(ok).unwrap().get_total_size(), 5);
+		assert_eq!(
+			export::from_json(&ok.replace("\"size\": 5,", "\"size\": 6,")).err(),
+			Some(export::JsonError::SizeMismatch("/".to_string()))
+		);
+		assert_eq!(
+			export::from_json(&ok.replace("\"size\": 0", "\"size\": 1")).err(),
+			Some(export::JsonError::SizeMismatch("/d".to_string()))
+		);
+		assert_eq!(
+			export::from_json(&ok.replace("\"files\": []", "\"files\": 1")).err(),
+			Some(export::JsonError::Shape("/d".to_string()))
+		);
+		assert_eq!(
+			export::from_json(&ok.replace("\"d\"", "\"a\"")).err(),
+			Some(export::JsonError::Device(DeviceError::ListingConflict(
+				"/".to_string()
+			)))
+		);
+		assert!(matches!(
+			export::from_json(&ok[..ok.len() - 1]),
+			Err(export::JsonError::Syntax(_))
+		));
+	}
+
+	#[ignore]
+	#[test]
+	fn arena_bench() {
+		let lines = generate(1_000_000, 1);
+		let now = std::time::Instant::now();
+		let dev = Device::from_lines(&lines).unwrap();
+		println!("{} dirs in {:?}", dev.dirs.len(), now.elapsed());
+	}
+
+	#[test]
+	fn listings() {
+		let fd = std::fs::File::open("example.txt").unwrap();
+		let mut lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.map(|l| l.unwrap())
+			.collect();
+		let dev = Device::from_lines(&lines).unwrap();
+		assert!(dev.unlisted().is_empty());
+		assert!(dev.is_size_known(ROOT));
+
+		// listing / again the same way is fine
+		lines.extend(
+			[
+				"$ cd /",
+				"$ ls",
+				"8504156 c.dat",
+				"dir a",
+				"dir d",
+				"14848514 b.txt",
+			]
+			.map(String::from),
+		);
+		let dev = Device::from_lines(&lines).unwrap();
+		assert_eq!(dev.get_total_size(), 48381165);
+
+		let mut conflicting = lines.clone();
+		conflicting.push("1 new.txt".to_string());
+		assert_eq!(
+			Device::from_lines(&conflicting).err(),
+			Some(DeviceError::ListingConflict("/".to_string()))
+		);
+		let mut conflicting = lines.clone();
+		conflicting.extend(["$ cd a/e", "$ ls", "585 i"].map(String::from));
+		assert_eq!(
+			Device::from_lines(&conflicting).err(),
+			Some(DeviceError::ListingConflict("/a/e".to_string()))
+		);
+		let mut conflicting = lines.clone();
+		conflicting.extend(["$ cd a/e", "$ ls"].map(String::from));
+		assert!(Device::from_lines(&conflicting).is_err());
+		let mut bad = lines.clone();
+		bad.push("$ rm -rf /".to_string());
+		assert_eq!(
+			Device::from_lines(&bad).err(),
+			Some(DeviceError::BadLine("$ rm -rf /".to_string()))
+		);
+		for name in ["", ".", "..", "a/b", "/", " x", "x ", "a\nb", "a\tb"] {
+			let mut dev = Device::new();
+			let err = Some(DeviceError::BadName(name.to_string()));
+			assert_eq!(dev.ls(&[Entry::Dir(name.to_string())]).err(), err);
+			assert_eq!(dev.ls(&[Entry::Var(name.to_string(), 1)]).err(), err);
+			assert_eq!(dev.add_dir(name).err(), err);
+			// nothing from the bad listing was kept
+			let entries = [
+				Entry::Var("ok".to_string(), 1),
+				Entry::Dir(name.to_string()),
+			];
+			assert_eq!(dev.ls(&entries).err(), err);
+			assert!(dev.dir(ROOT).vars().is_empty());
+		}
+		// a conflict late in the listing keeps nothing either
+		let mut dev = Device::new();
+		let entries = [
+			Entry::Dir("a".to_string()),
+			Entry::Var("big".to_string(), 100),
+			Entry::Var("a".to_string(), 5),
+		];
+		assert_eq!(
+			dev.ls(&entries).err(),
+			Some(DeviceError::ListingConflict("/".to_string()))
+		);
+		assert!(dev.dir(ROOT).dirs().is_empty() && dev.dir(ROOT).vars().is_empty());
+		assert_eq!(dev.get_total_size(), 0);
+		// a dir and a file can't share a name
+		dev.add_var("x", 3).unwrap();
+		assert_eq!(dev.add_dir("x").err(), Some(DeviceError::VarAlreadyExists));
+		dev.add_dir("y").unwrap();
+		assert_eq!(
+			dev.add_var("y", 1).err(),
+			Some(DeviceError::DirAlreadyExists)
+		);
+		let mut dev = Device::new();
+		dev.ls(&[Entry::Dir("x".to_string())]).unwrap();
+		assert!(dev.ls(&[Entry::Var("x".to_string(), 1)]).is_err());
+		let mut bad = lines.clone();
+		bad.extend(["$ cd a", "$ ls", "dir .."].map(String::from));
+		assert_eq!(
+			Device::from_lines(&bad).err(),
+			Some(DeviceError::BadName("..".to_string()))
+		);
+
+		// d is never listed
+		let lines: Vec<String> = ["$ cd /", "$ ls", "dir a", "dir d", "$ cd a", "$ ls", "5 x"]
+			.map(String::from)
+			.to_vec();
+		let dev = Device::from_lines(&lines).unwrap();
+		assert_eq!(dev.unlisted(), [dev.lookup("/d").unwrap()]);
+		assert!(dev.is_size_known(dev.lookup("/a").unwrap()));
+		assert!(!dev.is_size_known(ROOT));
+	}
 }
@@ -0,0 +1,73 @@
+use std::io::BufRead;
+
+use day_07::{export, query, Device};
+
+const USAGE: &str = "\
+usage: aoc day07 <input> <command>
+input is a terminal session, or a .json snapshot
+commands:
+  tree                          filesystem with sizes
+  du                            dirs by size, largest first
+  find [-name GLOB] [-size +N|-N] [-type d|f]
+  top K                         K largest files
+  json                          snapshot as JSON
+  transcript                    canonical terminal session";
+
+fn run(args: &[String]) -> Result<String, String> {
+	let [day, input, command, rest @ ..] = args else {
+		return Err(USAGE.to_string());
+	};
+	if day != "day07" {
+		return Err(format!("unknown day {day}\n{USAGE}"));
+	}
+	let dev = if input.ends_with(".json") {
+		let text = std::fs::read_to_string(input).map_err(|e| format!("{input}: {e}"))?;
+		export::from_json(&text).map_err(|e| format!("{input}: {e:?}"))?
+	} else {
+		let fd = std::fs::File::open(input).map_err(|e| format!("{input}: {e}"))?;
+		let lines: Vec<String> = std::io::BufReader::new(fd)
+			.lines()
+			.collect::<Result<_, _>>()
+			.map_err(|e| format!("{input}: {e}"))?;
+		Device::from_lines(&lines).map_err(|e| format!("{input}: {e:?}"))?
+	};
+	let mut out = String::new();
+	match (command.as_str(), rest) {
+		("tree", []) => out = query::tree(&dev),
+		("du", []) => {
+			for f in query::du(&dev) {
+				out += &format!("{}\t{}\n", query::human(f.size), f.path);
+			}
+		}
+		("find", filters) => {
+			for f in query::find(&dev, &query::Filter::parse(filters)?) {
+				out += &format!("{}\n", f.path);
+			}
+		}
+		("top", [k]) => {
+			let k = k.parse().map_err(|_| format!("bad count {k}"))?;
+			for f in query::top_files(&dev, k) {
+				out += &format!("{}\t{}\n", f.size, f.path);
+			}
+		}
+		("json", []) => out = export::to_json(&dev),
+		("transcript", []) => {
+			for line in export::transcript(&dev) {
+				out += &format!("{line}\n");
+			}
+		}
+		_ => return Err(USAGE.to_string()),
+	}
+	Ok(out)
+}
+
+fn main() {
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	match run(&args) {
+		Ok(out) => print!("{out}"),
+		Err(e) => {
+			eprintln!("{e}");
+			std::process::exit(1);
+		}
+	}
+}
@@ -0,0 +1,241 @@
+//! Questions about a reconstructed `Device`, in the spirit of `tree`, `du` and `find`.
+
+use crate::{Device, DirId, ROOT};
+
+/// A file or dir found by a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Found {
+	pub path: String,
+	pub size: usize,
+	pub is_dir: bool,
+	/// 0 for the root
+	pub depth: usize,
+}
+
+/// Path of `name` inside the dir at `dir_path`
+fn join(dir_path: &str, name: &str) -> String {
+	if dir_path == "/" {
+		format!("/{name}")
+	} else {
+		format!("{dir_path}/{name}")
+	}
+}
+
+/// Entry seen by `walk`, cheap to make: the path is only built by `found`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Node<'a> {
+	/// the dir itself, or the dir holding the file
+	pub id: DirId,
+	/// `/` for the root
+	pub name: &'a str,
+	pub size: usize,
+	pub is_dir: bool,
+	/// 0 for the root
+	pub depth: usize,
+}
+
+impl Node<'_> {
+	pub fn path(&self, dev: &Device) -> String {
+		if self.is_dir {
+			dev.path(self.id)
+		} else {
+			join(&dev.path(self.id), self.name)
+		}
+	}
+
+	pub fn found(&self, dev: &Device) -> Found {
+		Found {
+			path: self.path(dev),
+			size: self.size,
+			is_dir: self.is_dir,
+			depth: self.depth,
+		}
+	}
+}
+
+/// Every dir and file, depth first with the entries of a dir sorted by name
+pub fn walk(dev: &Device) -> Vec<Node<'_>> {
+	let mut v = vec![];
+	let mut todo = vec![Node {
+		id: ROOT,
+		name: "/",
+		size: dev.dir(ROOT).size(),
+		is_dir: true,
+		depth: 0,
+	}];
+	while let Some(node) = todo.pop() {
+		if node.is_dir {
+			let dir = dev.dir(node.id);
+			let dirs = dir
+				.dirs()
+				.iter()
+				.map(|(name, &sub)| (name.as_str(), dev.dir(sub).size(), sub, true));
+			let vars = dir
+				.vars()
+				.iter()
+				.map(|(name, &size)| (name.as_str(), size, node.id, false));
+			let mut entries: Vec<_> = dirs.chain(vars).collect();
+			entries.sort();
+			// reversed, so they come off the stack in order
+			for (name, size, id, is_dir) in entries.into_iter().rev() {
+				todo.push(Node {
+					id,
+					name,
+					size,
+					is_dir,
+					depth: node.depth + 1,
+				});
+			}
+		}
+		v.push(node);
+	}
+	v
+}
+
+/// The `k` largest, ties by path, building paths only for those and their ties
+fn largest(dev: &Device, mut nodes: Vec<Node>, k: usize) -> Vec<Found> {
+	nodes.sort_by_key(|n| std::cmp::Reverse(n.size));
+	if let Some(last) = nodes.get(k.saturating_sub(1)) {
+		let cut = last.size;
+		nodes.retain(|n| n.size >= cut);
+	}
+	let mut v: Vec<Found> = nodes.iter().map(|n| n.found(dev)).collect();
+	v.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+	v.truncate(k);
+	v
+}
+
+/// The puzzle's drawing of the filesystem:
+/// ```text
+/// - / (dir, size=48381165)
+///   - a (dir, size=94853)
+///     - b.txt (file, size=14848514)
+/// ```
+pub fn tree(dev: &Device) -> String {
+	let mut s = String::new();
+	for n in walk(dev) {
+		let kind = if n.is_dir { "dir" } else { "file" };
+		let indent = "  ".repeat(n.depth);
+		s += &format!("{indent}- {} ({kind}, size={})\n", n.name, n.size);
+	}
+	s
+}
+
+/// Every dir, largest first (ties by path)
+pub fn du(dev: &Device) -> Vec<Found> {
+	let dirs: Vec<Node> = walk(dev).into_iter().filter(|n| n.is_dir).collect();
+	let k = dirs.len();
+	largest(dev, dirs, k)
+}
+
+/// Size the way `du -h` prints it: `584`, `1.5K`, `23M`
+pub fn human(size: usize) -> String {
+	const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+	if size < 1024 {
+		return size.to_string();
+	}
+	let mut value = size as f64 / 1024.0;
+	let mut unit = 0;
+	while value >= 1024.0 && unit < UNITS.len() - 1 {
+		value /= 1024.0;
+		unit += 1;
+	}
+	if value < 10.0 {
+		format!("{value:.1}{}", UNITS[unit])
+	} else {
+		format!("{value:.0}{}", UNITS[unit])
+	}
+}
+
+/// `find` style test on an entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+	/// glob on the last path component, `*` and `?` wildcards
+	Name(String),
+	/// strictly larger than
+	SizeAbove(usize),
+	/// strictly smaller than
+	SizeBelow(usize),
+	Dirs,
+	Files,
+}
+
+impl Filter {
+	/// Parse `find` arguments: `-name <glob>`, `-size +<n>`/`-size -<n>`, `-type d|f`
+	pub fn parse(args: &[String]) -> Result<Vec<Filter>, String> {
+		let mut filters = vec![];
+		let mut args = args.iter();
+		while let Some(arg) = args.next() {
+			let value = args.next().ok_or(format!("{arg} needs a value"))?;
+			let bad = || format!("bad value for {arg}: {value}");
+			let filter = match (arg.as_str(), value.as_str()) {
+				("-name", glob) => Filter::Name(glob.to_string()),
+				("-size", size) => {
+					if let Some(n) = size.strip_prefix('+') {
+						Filter::SizeAbove(n.parse().map_err(|_| bad())?)
+					} else if let Some(n) = size.strip_prefix('-') {
+						Filter::SizeBelow(n.parse().map_err(|_| bad())?)
+					} else {
+						return Err(bad());
+					}
+				}
+				("-type", "d") => Filter::Dirs,
+				("-type", "f") => Filter::Files,
+				("-type", _) => return Err(bad()),
+				_ => return Err(format!("unknown filter {arg}")),
+			};
+			filters.push(filter);
+		}
+		Ok(filters)
+	}
+
+	pub fn matches(&self, node: &Node) -> bool {
+		match self {
+			Filter::Name(glob) => glob_match(glob, node.name),
+			Filter::SizeAbove(n) => node.size > *n,
+			Filter::SizeBelow(n) => node.size < *n,
+			Filter::Dirs => node.is_dir,
+			Filter::Files => !node.is_dir,
+		}
+	}
+}
+
+/// Whether `name` matches `glob`, where `*` is any run of chars and `?` any one char
+pub fn glob_match(glob: &str, name: &str) -> bool {
+	let (glob, name): (Vec<char>, Vec<char>) = (glob.chars().collect(), name.chars().collect());
+	let (mut g, mut n) = (0, 0);
+	// where the last `*` was, and how much of name it swallowed
+	let mut star: Option<(usize, usize)> = None;
+	while n < name.len() {
+		if g < glob.len() && (glob[g] == '?' || glob[g] == name[n]) {
+			g += 1;
+			n += 1;
+		} else if g < glob.len() && glob[g] == '*' {
+			star = Some((g, n));
+			g += 1;
+		} else if let Some((star_g, star_n)) = star {
+			// let the `*` swallow one more char
+			g = star_g + 1;
+			n = star_n + 1;
+			star = Some((star_g, n));
+		} else {
+			return false;
+		}
+	}
+	glob[g..].iter().all(|&c| c == '*')
+}
+
+/// Every entry passing all filters, in `walk` order
+pub fn find(dev: &Device, filters: &[Filter]) -> Vec<Found> {
+	walk(dev)
+		.into_iter()
+		.filter(|n| filters.iter().all(|filter| filter.matches(n)))
+		.map(|n| n.found(dev))
+		.collect()
+}
+
+/// The `k` largest files (ties by path)
+pub fn top_files(dev: &Device, k: usize) -> Vec<Found> {
+	let files = walk(dev).into_iter().filter(|n| !n.is_dir).collect();
+	largest(dev, files, k)
+}